@@ -1,13 +1,106 @@
-use std::{fmt, result::Result};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    result::Result,
+    sync::Arc,
+};
 
-use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use chrono::{
+    Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, Timelike, Weekday,
+};
+use serde::{
+    de::{DeserializeOwned, Error as _},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
+#[cfg(feature = "toml")]
+use crate::crate_parameters::parse_time_window;
+#[cfg(feature = "tz")]
+use crate::rule::relative_to_absolute_rules_in_range;
 use crate::{
+    crate_parameters::{parse_crate_datetime, parse_iso_datetime},
+    error::AvailabilityError,
     frame::Frame,
-    rule::{relative_to_absolute_rules, Rule},
+    ical,
+    rule::{iter_absolute_rules_in_range, Rule},
+    weekdays::Weekdays,
 };
 
+/// Callback invoked for each finalized frame, see `Availability::set_frame_hook`.
+pub type FrameHook<T> = Box<dyn Fn(&Frame<T>) + Send + Sync>;
+
+/// Frame hook as stored, shared between clones of an `Availability`.
+type SharedFrameHook<T> = Arc<dyn Fn(&Frame<T>) + Send + Sync>;
+
+/// Frames as parallel columns of starts, ends, off flags and JSON payloads.
+#[cfg(feature = "columnar")]
+pub type FrameColumns = (
+    Vec<NaiveDateTime>,
+    Vec<NaiveDateTime>,
+    Vec<bool>,
+    Vec<Option<serde_json::Value>>,
+);
+
+/// Status of a whole calendar day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayStatus {
+    /// Open for the entire day.
+    FullyOpen,
+    /// Closed for the entire day.
+    FullyClosed,
+    /// Open for part of the day.
+    Partial,
+}
+
+/// Owned copy of the rule set of an `Availability`, see `Availability::snapshot`.
+///
+/// The base rule, and thereby the base state, is part of the rules.
+#[derive(Debug, Clone)]
+pub struct Snapshot<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    pub rules: Vec<Vec<Rule<T>>>,
+}
+
+/// Version of the JSON format written by `Availability::to_json`.
+pub const JSON_FORMAT_VERSION: u64 = 1;
+
+/// Serialized representation of an `Availability`, as read back when deserializing.
+/// Generated frames are not included.
+#[derive(Deserialize)]
+#[serde(bound = "")]
+struct AvailabilityJson<T>
+where
+    // Equivalent to `for<'de> Deserialize<'de>`, which clashes with the derived `'de`
+    T: Serialize + DeserializeOwned + Clone,
+{
+    version: u64,
+    rules: Vec<Vec<Rule<T>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timezone_label: Option<String>,
+    #[serde(default)]
+    closed_exceptions_only: bool,
+    #[serde(default)]
+    include_end_marker: bool,
+}
+
+/// JSON representation of a generated frame, see `Availability::frames_to_json`.
+#[derive(Serialize)]
+struct FrameJson<'a, T>
+where
+    T: Serialize,
+{
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    open: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: &'a Option<T>,
+}
+
 /// Represents the availability schedule with priority-based rules.
 ///
 /// The `Availability` struct manages a collection of rules that define availability
@@ -17,8 +110,10 @@ use crate::{
 ///
 /// Start and end for rules and frames are always inclusive and exclusive respectively.
 ///
-/// Note that the base rule is always present at priority 0, and it is always "off" (closed).
-/// You cannot add, remove, or modify the base rule.
+/// Note that the base rule is always present at priority 0 and covers all supported dates.
+/// It is "off" (closed) unless the schedule was created open by default with
+/// `always_open_except` or `closed_exceptions_mode`, which set it "on". Apart from that
+/// status, you cannot add, remove, or modify the base rule.
 ///
 /// # Type Parameters
 ///
@@ -33,6 +128,142 @@ where
 {
     pub rules: Vec<Vec<Rule<T>>>,
     pub(crate) frames: Vec<Frame<T>>,
+    pub(crate) frame_hook: Option<SharedFrameHook<T>>,
+    pub(crate) include_end_marker: bool,
+    pub(crate) timezone_label: Option<String>,
+    pub(crate) closed_exceptions_only: bool,
+}
+
+/// Read-only `Availability`, see `Availability::freeze`.
+///
+/// Derefs to `Availability`, so every query method is available, but no mutator since
+/// those take `&mut self`:
+///
+/// ```compile_fail
+/// # use availability::availability::Availability;
+/// # use availability::rulebuilder::RuleBuilder;
+/// let mut frozen = Availability::<String>::new().freeze();
+/// let rule = RuleBuilder::new()
+///     .start_time_str("240101090000")
+///     .end_time_str("240101170000")
+///     .build()
+///     .unwrap();
+/// frozen.add_rule(rule, 1).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct FrozenAvailability<T>(Availability<T>)
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone;
+
+impl<T> Deref for FrozenAvailability<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    type Target = Availability<T>;
+
+    fn deref(&self) -> &Availability<T> {
+        &self.0
+    }
+}
+
+/// FNV-1a hasher with a fixed offset basis, so hashes are stable across runs.
+struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    fn new() -> Self {
+        StableHasher(Self::OFFSET_BASIS)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+}
+
+impl<T> Clone for Availability<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+    Rule<T>: Clone,
+{
+    fn clone(&self) -> Self {
+        Availability {
+            rules: self.rules.clone(),
+            frames: self.frames.clone(),
+            frame_hook: self.frame_hook.clone(),
+            include_end_marker: self.include_end_marker,
+            timezone_label: self.timezone_label.clone(),
+            closed_exceptions_only: self.closed_exceptions_only,
+        }
+    }
+}
+
+/// Serializes the rules tagged with `JSON_FORMAT_VERSION`, like `Availability::to_json`.
+/// Settings are only written when they differ from the default. Generated frames are
+/// derived state and the frame hook cannot be serialized, so neither is included.
+impl<T> Serialize for Availability<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let len = 2
+            + usize::from(self.timezone_label.is_some())
+            + usize::from(self.closed_exceptions_only)
+            + usize::from(self.include_end_marker);
+        let mut state = serializer.serialize_struct("Availability", len)?;
+        state.serialize_field("version", &JSON_FORMAT_VERSION)?;
+        state.serialize_field("rules", &self.rules)?;
+        if let Some(label) = &self.timezone_label {
+            state.serialize_field("timezone_label", label)?;
+        }
+        if self.closed_exceptions_only {
+            state.serialize_field("closed_exceptions_only", &true)?;
+        }
+        if self.include_end_marker {
+            state.serialize_field("include_end_marker", &true)?;
+        }
+        state.end()
+    }
+}
+
+/// Deserializes a schedule written by the `Serialize` implementation, checking the
+/// format version and rules like `Availability::from_json`. Frames start out empty.
+impl<'de, T> Deserialize<'de> for Availability<T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let parsed = AvailabilityJson::<T>::deserialize(deserializer)?;
+        Availability::from_parsed(parsed).map_err(D::Error::custom)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Availability<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    type Item = &'a Frame<T>;
+    type IntoIter = std::slice::Iter<'a, Frame<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter()
+    }
 }
 
 impl<T> fmt::Display for Availability<T>
@@ -42,6 +273,9 @@ where
     Frame<T>: fmt::Display,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(label) = &self.timezone_label {
+            writeln!(f, "Timezone: {}", label)?;
+        }
         writeln!(f, "Availability Frames:")?;
         for frame in &self.frames {
             writeln!(f, "  {}", frame)?;
@@ -58,18 +292,398 @@ where
     /// Creates a new, empty `Availability` instance.
     ///
     /// Initializes an `Availability` with no rules. The base rule is automatically included
-    /// with the lowest priority to cover all possible date-times as "off" (closed).
+    /// with the lowest priority to cover all possible date-times as "off" (closed). See
+    /// `always_open_except` and `closed_exceptions_mode` for schedules open by default.
     pub fn new() -> Self {
-        Availability {
+        Availability::from_snapshot(Snapshot {
             rules: vec![vec![Rule::base_rule()]],
+        })
+    }
+
+    /// Creates a new, empty `Availability` with room for as many rules at each priority
+    /// as `other` has, e.g. to rebuild an edited copy of `other` without reallocating.
+    ///
+    /// The priority levels of `other` are created up front but left empty.
+    pub fn with_rules_capacity_from(other: &Availability<T>) -> Self {
+        let mut rules = Vec::with_capacity(other.rules.len());
+        rules.push(vec![Rule::base_rule()]);
+        rules.extend(
+            other
+                .rules
+                .iter()
+                .skip(1)
+                .map(|level| Vec::with_capacity(level.len())),
+        );
+        Availability::from_snapshot(Snapshot { rules })
+    }
+
+    /// Creates an `Availability` that is open by default and closed during the given
+    /// windows, e.g. open around the clock except for maintenance.
+    ///
+    /// The base rule is set to "on" and an "off" rule is added at priority 1 for each
+    /// window. Windows are clipped to the range of the base rule, overlapping windows are
+    /// merged and empty windows are ignored.
+    pub fn always_open_except(windows: &[(NaiveDateTime, NaiveDateTime)]) -> Self {
+        let mut availability = Availability::new();
+        availability.set_base_off(false);
+
+        let base_rule = Rule::<T>::base_rule();
+        let mut windows: Vec<(NaiveDateTime, NaiveDateTime)> = windows
+            .iter()
+            .map(|&(start, end)| (start.max(base_rule.start), end.min(base_rule.end)))
+            .filter(|(start, end)| start < end)
+            .collect();
+        windows.sort();
+        let mut merged: Vec<(NaiveDateTime, NaiveDateTime)> = Vec::with_capacity(windows.len());
+        for (start, end) in windows {
+            match merged.last_mut() {
+                Some(last) if start < last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        for (start, end) in merged {
+            let rule = Rule::new(start, end, None, true, None).unwrap();
+            availability.add_rule(rule, 1).unwrap();
+        }
+        availability
+    }
+
+    /// Creates an `Availability` that is open by default, where rules only ever close.
+    ///
+    /// The base rule is set to "on" and `add_rule` rejects "on" rules, since they would
+    /// be redundant. See also `always_open_except`.
+    pub fn closed_exceptions_mode() -> Self {
+        let mut availability = Availability::new();
+        availability.set_base_off(false);
+        availability.closed_exceptions_only = true;
+        availability
+    }
+
+    /// Creates an `Availability` with an "on" rule at `priority` for each of the given
+    /// windows. Fails if a window is empty or overlaps another.
+    pub fn from_open_windows(
+        windows: &[(NaiveDateTime, NaiveDateTime)],
+        priority: usize,
+    ) -> Result<Self, AvailabilityError> {
+        let mut availability = Availability::new();
+        for &(start, end) in windows {
+            let rule = Rule::new(start, end, None, false, None)?;
+            availability.add_rule(rule, priority)?;
+        }
+        Ok(availability)
+    }
+
+    /// Creates an `Availability` with an "on" rule at `priority` for each VEVENT in an
+    /// iCalendar document.
+    ///
+    /// Event datetimes are taken as written, ignoring any timezone. Recurring events are
+    /// not supported and result in an error, as do events overlapping each other.
+    pub fn from_ical(ics: &str, priority: usize) -> Result<Self, AvailabilityError> {
+        let mut availability = Availability::new();
+        for (start, end) in ical::parse_events(ics)? {
+            let rule = Rule::new(start, end, None, false, None)?;
+            availability.add_rule(rule, priority)?;
+        }
+        Ok(availability)
+    }
+
+    /// Creates an `Availability` from a TOML table of weekly opening hours, e.g.
+    ///
+    /// ```toml
+    /// monday = ["09:00-12:00", "13:00-17:00"]
+    /// saturday = ["10:00-14:00"]
+    /// ```
+    ///
+    /// Each window becomes an "on" relative rule, active from `from` through `until`
+    /// (inclusive). Days sharing a window share a rule, and absent days stay closed. Since
+    /// relative rules sharing a weekday cannot share a priority, the first window of each
+    /// day is put at `priority`, the second at `priority + 1` and so on. Fails on unknown
+    /// days, invalid windows or windows overlapping within a day.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(
+        toml: &str,
+        from: NaiveDate,
+        until: NaiveDate,
+        priority: usize,
+    ) -> Result<Self, AvailabilityError> {
+        if priority == 0 {
+            return Err(AvailabilityError::ReservedPriority);
+        }
+        if from >= until {
+            return Err(AvailabilityError::InvalidRule(
+                "from must be before until".to_string(),
+            ));
+        }
+        let table: toml::Table = toml
+            .parse()
+            .map_err(|error: toml::de::Error| AvailabilityError::InvalidToml(error.to_string()))?;
+
+        // Keyed by the position of the window within its day, then the window itself
+        let mut weekdays_by_window: BTreeMap<(usize, (NaiveTime, NaiveTime)), Weekdays> =
+            BTreeMap::new();
+        for (day, value) in table.iter() {
+            let weekday = Weekdays::from_day(day)
+                .ok_or_else(|| AvailabilityError::InvalidToml(format!("Unknown day: {}", day)))?;
+            let values = value.as_array().ok_or_else(|| {
+                AvailabilityError::InvalidToml(format!("Expected a list of windows for {}", day))
+            })?;
+            let mut windows = Vec::new();
+            for value in values {
+                let window = value.as_str().ok_or_else(|| {
+                    AvailabilityError::InvalidToml(format!("Expected a window string for {}", day))
+                })?;
+                windows.push(parse_time_window(window)?);
+            }
+
+            // A window until midnight overlaps every window starting after it
+            windows.sort();
+            for pair in windows.windows(2) {
+                let ((_, end), (next_start, _)) = (pair[0], pair[1]);
+                if end == NaiveTime::MIN || end > next_start {
+                    return Err(AvailabilityError::InvalidRule(format!(
+                        "Windows overlap on {}",
+                        day
+                    )));
+                }
+            }
+            for (position, window) in windows.into_iter().enumerate() {
+                *weekdays_by_window
+                    .entry((position, window))
+                    .or_insert_with(Weekdays::empty) |= weekday;
+            }
+        }
+
+        let mut availability = Availability::new();
+        availability.rules.resize_with(priority + 1, Vec::new);
+        for ((position, (open, close)), weekdays) in weekdays_by_window {
+            // A window until midnight ends at the start of the day after `until`
+            let last_day = match close {
+                NaiveTime::MIN => until.succ_opt().unwrap_or(until),
+                _ => until,
+            };
+            let rule = Rule::new(
+                from.and_time(open),
+                last_day.and_time(close),
+                Some(weekdays),
+                false,
+                None,
+            )?;
+            availability.add_rule(rule, priority + position)?;
+        }
+        Ok(availability)
+    }
+
+    /// Creates an `Availability` from a snapshot of rules. No frames are generated.
+    pub fn from_snapshot(snapshot: Snapshot<T>) -> Self {
+        Availability {
+            rules: snapshot.rules,
             frames: Vec::new(),
+            frame_hook: None,
+            include_end_marker: false,
+            timezone_label: None,
+            closed_exceptions_only: false,
+        }
+    }
+
+    /// Sets the status of the base rule, i.e. whether the schedule is closed wherever no
+    /// custom rule applies. The only change ever made to the base rule.
+    fn set_base_off(&mut self, off: bool) {
+        self.rules[0][0].off = off;
+    }
+
+    /// True if the schedule is closed wherever no custom rule applies, which is the
+    /// default. See `always_open_except`.
+    pub fn is_base_off(&self) -> bool {
+        self.rules
+            .first()
+            .and_then(|rules| rules.first())
+            .is_none_or(|rule| rule.off)
+    }
+
+    /// Serializes the rules and settings to JSON, tagged with `JSON_FORMAT_VERSION`.
+    /// Generated frames and the frame hook are not included.
+    ///
+    /// Same as serializing the `Availability` with serde into JSON.
+    pub fn to_json(&self) -> Result<String, AvailabilityError> {
+        serde_json::to_string(self).map_err(|e| AvailabilityError::InvalidJson(e.to_string()))
+    }
+
+    /// Serializes the generated frames to a JSON array, e.g. to serve frames computed once
+    /// to many clients. Each frame has ISO 8601 `start` and `end` timestamps, an `open`
+    /// flag and its `payload`, if any. The rules are not included.
+    pub fn frames_to_json(&self) -> Result<String, AvailabilityError> {
+        let frames: Vec<FrameJson<T>> = self
+            .frames
+            .iter()
+            .map(|frame| FrameJson {
+                start: frame.start,
+                end: frame.end,
+                open: frame.is_on(),
+                payload: &frame.payload,
+            })
+            .collect();
+        serde_json::to_string(&frames).map_err(|e| AvailabilityError::InvalidJson(e.to_string()))
+    }
+
+    /// Computes the closed intervals within `[start, end)` as busy blocks in the shape of
+    /// Google Calendar's FreeBusy API, i.e. `{"busy": [{"start": ..., "end": ...}]}`.
+    ///
+    /// Datetimes are taken to be in UTC and written in RFC 3339. Adjacent closed frames
+    /// are merged into one block. Nothing is stored.
+    pub fn to_freebusy_json(&self, start: NaiveDateTime, end: NaiveDateTime) -> String {
+        let rfc3339 = |datetime: NaiveDateTime| {
+            datetime
+                .and_utc()
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        };
+        let busy: Vec<serde_json::Value> = sessions(&self.compute_frames(start, end), true)
+            .into_iter()
+            .map(|(start, end)| serde_json::json!({"start": rfc3339(start), "end": rfc3339(end)}))
+            .collect();
+        serde_json::json!({ "busy": busy }).to_string()
+    }
+
+    /// Deserializes a schedule written by `to_json`. No frames are generated.
+    ///
+    /// Fails if the data has a format version other than `JSON_FORMAT_VERSION`, does not
+    /// hold the base rule at priority 0, or holds rules `add_rule` would reject, e.g.
+    /// overlapping rules or "on" rules in closed exceptions mode.
+    pub fn from_json(json: &str) -> Result<Self, AvailabilityError> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| AvailabilityError::InvalidJson(e.to_string()))?;
+        let version = value
+            .get("version")
+            .and_then(|version| version.as_u64())
+            .ok_or_else(|| AvailabilityError::InvalidJson("missing version".to_string()))?;
+        if version != JSON_FORMAT_VERSION {
+            return Err(AvailabilityError::UnsupportedVersion(version));
+        }
+
+        let parsed: AvailabilityJson<T> = serde_json::from_value(value)
+            .map_err(|e| AvailabilityError::InvalidJson(e.to_string()))?;
+        Availability::from_parsed(parsed)
+    }
+
+    /// Validates and restores a deserialized schedule. No frames are generated.
+    ///
+    /// The custom rules are added one by one with `add_rule`, so the data cannot hold
+    /// rules `add_rule` would have rejected.
+    fn from_parsed(parsed: AvailabilityJson<T>) -> Result<Self, AvailabilityError>
+    where
+        T: DeserializeOwned,
+    {
+        if parsed.version != JSON_FORMAT_VERSION {
+            return Err(AvailabilityError::UnsupportedVersion(parsed.version));
+        }
+        let mut levels = parsed.rules.into_iter();
+        let mut availability = Availability::from_snapshot(Snapshot {
+            rules: levels.next().into_iter().collect(),
+        });
+        availability.timezone_label = parsed.timezone_label;
+        availability.closed_exceptions_only = parsed.closed_exceptions_only;
+        availability.include_end_marker = parsed.include_end_marker;
+        availability.validate_base_rule_invariant()?;
+
+        for (priority, rules) in levels.enumerate().map(|(i, rules)| (i + 1, rules)) {
+            // Empty priorities are kept as they were serialized
+            availability.rules.resize_with(priority + 1, Vec::new);
+            for rule in rules {
+                availability.add_rule(rule, priority)?;
+            }
+        }
+        Ok(availability)
+    }
+
+    /// Labels the schedule with the timezone its naive datetimes are meant in, e.g.
+    /// `"Europe/Stockholm"`. The label is only informational and included in JSON and
+    /// `Display` output, it does not affect frame generation.
+    pub fn with_timezone_label(mut self, label: &str) -> Self {
+        self.timezone_label = Some(label.to_string());
+        self
+    }
+
+    /// The timezone label set with `with_timezone_label`, if any.
+    pub fn timezone_label(&self) -> Option<&str> {
+        self.timezone_label.as_deref()
+    }
+
+    /// Takes an owned copy of the rules, e.g. to store versions of a schedule.
+    /// Use together with `content_hash` to identify versions.
+    pub fn snapshot(&self) -> Snapshot<T> {
+        Snapshot {
+            rules: self.rules.clone(),
         }
     }
 
+    /// Iterates over all rules, including the base rule, paired with their priority.
+    /// Rules are ordered by priority, lowest first.
+    pub fn iter_rules(&self) -> impl Iterator<Item = (usize, &Rule<T>)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .flat_map(|(priority, rules)| rules.iter().map(move |rule| (priority, rule)))
+    }
+
+    /// Number of rules at `priority`, or 0 if nothing was ever added there.
+    pub fn rule_count_at(&self, priority: usize) -> usize {
+        self.rules.get(priority).map_or(0, Vec::len)
+    }
+
+    /// Priorities holding at least one rule, lowest first. Priority 0, which holds the base
+    /// rule, is always included.
+    pub fn priorities(&self) -> impl Iterator<Item = usize> + '_ {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rules)| !rules.is_empty())
+            .map(|(priority, _)| priority)
+    }
+
+    /// Owned copies of the rules paired with their priority, e.g. for export to a flat
+    /// table. The base rule is only included if `include_base` is set.
+    pub fn flatten_rules(&self, include_base: bool) -> Vec<(usize, Rule<T>)> {
+        self.iter_rules()
+            .filter(|(priority, _)| include_base || *priority > 0)
+            .map(|(priority, rule)| (priority, rule.clone()))
+            .collect()
+    }
+
+    /// True if both schedules have the same rules at the same priorities. The base rule
+    /// and generated frames are not compared.
+    pub fn equal_rules(&self, other: &Availability<T>) -> bool {
+        let levels = self.rules.len().max(other.rules.len());
+        (1..levels).all(|priority| {
+            let ours = self.rules.get(priority).map_or(&[][..], |rules| rules);
+            let theirs = other.rules.get(priority).map_or(&[][..], |rules| rules);
+            ours.len() == theirs.len()
+                && ours
+                    .iter()
+                    .zip(theirs.iter())
+                    .all(|(rule, other_rule)| rule.is_identical_to(other_rule))
+        })
+    }
+
+    /// Checks a rule on its own, regardless of priority and other rules: it must have a
+    /// non-zero duration, only valid weekday bits and lie within the range of the base
+    /// rule. These are the checks `Rule::new` and `add_rule` make, `add_rule`
+    /// additionally checks overlaps at the priority.
+    pub fn validate_rule(&self, rule: &Rule<T>) -> Result<(), AvailabilityError> {
+        rule.validate()
+    }
+
     /// Adds a new rule with the specified priority.
-    pub fn add_rule(&mut self, rule: Rule<T>, priority: usize) -> Result<(), String> {
+    ///
+    /// Fails if the rule is invalid on its own, see `validate_rule`, or clashes with a rule
+    /// at the priority.
+    pub fn add_rule(&mut self, rule: Rule<T>, priority: usize) -> Result<(), AvailabilityError> {
         if priority == 0 {
-            return Err("Priority 0 is reserved for base rule and cannot be modified".to_string());
+            return Err(AvailabilityError::ReservedPriority);
+        }
+        // Rule fields are public, so the rule may have been changed since `Rule::new`
+        self.validate_rule(&rule)?;
+        if self.closed_exceptions_only && !rule.off {
+            return Err(AvailabilityError::OnRuleInClosedExceptionsMode);
         }
 
         while self.rules.len() <= priority {
@@ -89,21 +703,29 @@ where
             match (overlaps, existing_rule.is_absolute()) {
                 // Overlaps + Absolute
                 (true, true) => {
-                    return Err(format!(
-                        "New rule overlaps with existing rule at priority {}. \
-                    New rule: {:?} to {:?}, Existing rule: {:?} to {:?}",
-                        priority, rule.start, rule.end, existing_rule.start, existing_rule.end
-                    ))
+                    return Err(AvailabilityError::AbsoluteOverlap {
+                        priority,
+                        new_start: rule.start,
+                        new_end: rule.end,
+                        existing_start: existing_rule.start,
+                        existing_end: existing_rule.end,
+                    })
                 }
                 // Overlaps + Relative
                 (true, false) => {
                     // Only add new rule if none of the weekdays are in existing rule
                     if existing_rule.has_weekdays_in(&rule) {
-                        return Err(format!(
-                        "New rule overlaps with existing rule at priority {} because of clashing weekdays. \
-                        New rule: {:?} to {:?}, Existing rule: {:?} to {:?}",
-                        priority, rule.start, rule.end, existing_rule.start, existing_rule.end
-                    ));
+                        // Re-adding an identical rule is a no-op, e.g. on config reload
+                        if existing_rule.is_identical_to(&rule) {
+                            return Ok(());
+                        }
+                        return Err(AvailabilityError::WeekdayClash {
+                            priority,
+                            new_start: rule.start,
+                            new_end: rule.end,
+                            existing_start: existing_rule.start,
+                            existing_end: existing_rule.end,
+                        });
                     }
                 }
                 // No overlap + Absolute or Relative
@@ -116,29 +738,189 @@ where
         Ok(())
     }
 
+    /// Freezes the schedule, preventing any further changes to its rules or frames.
+    ///
+    /// Generate the frames needed before freezing, since that is a mutation too.
+    pub fn freeze(self) -> FrozenAvailability<T> {
+        FrozenAvailability(self)
+    }
+
+    /// Checks that priority 0 holds exactly one rule, equal to the base rule.
+    ///
+    /// The status of the base rule is not compared, only its range, weekdays and payload.
+    pub fn validate_base_rule_invariant(&self) -> Result<(), AvailabilityError> {
+        let base_rules = self.rules.first().ok_or_else(|| {
+            AvailabilityError::BaseRuleInvariant("priority 0 is missing".to_string())
+        })?;
+        if base_rules.len() != 1 {
+            return Err(AvailabilityError::BaseRuleInvariant(format!(
+                "priority 0 holds {} rules, expected 1",
+                base_rules.len()
+            )));
+        }
+
+        let rule = &base_rules[0];
+        let base_rule = Rule::<T>::base_rule();
+        if rule.start != base_rule.start
+            || rule.end != base_rule.end
+            || rule.weekdays != base_rule.weekdays
+            || rule.payload.is_some()
+        {
+            return Err(AvailabilityError::BaseRuleInvariant(format!(
+                "priority 0 holds {}, expected the base rule",
+                rule
+            )));
+        }
+        Ok(())
+    }
+
+    /// The rules at `priority` that would make adding `rule` there fail, paired with
+    /// their index, e.g. to show conflicts before adding a rule.
+    pub fn rules_overlapping_rule(
+        &self,
+        rule: &Rule<T>,
+        priority: usize,
+    ) -> Vec<(usize, &Rule<T>)> {
+        let Some(rules) = self.rules.get(priority) else {
+            return Vec::new();
+        };
+        rules
+            .iter()
+            .enumerate()
+            .filter(|(_, existing_rule)| {
+                // Same conditions as in add_rule
+                existing_rule.datetime_overlaps_with(rule)
+                    && (existing_rule.is_absolute()
+                        || (existing_rule.has_weekdays_in(rule)
+                            && !existing_rule.is_identical_to(rule)))
+            })
+            .collect()
+    }
+
+    /// Adds an "on" rule from `from` until the end of the base rule, i.e. open until
+    /// further notice.
+    pub fn open_until(
+        &mut self,
+        from: NaiveDateTime,
+        priority: usize,
+    ) -> Result<(), AvailabilityError> {
+        let rule = Rule::new(from, Rule::<T>::base_rule().end, None, false, None)?;
+        self.add_rule(rule, priority)
+    }
+
+    /// Adds an "off" rule from `from` until the end of the base rule, i.e. closed until
+    /// further notice.
+    pub fn close_until(
+        &mut self,
+        from: NaiveDateTime,
+        priority: usize,
+    ) -> Result<(), AvailabilityError> {
+        let rule = Rule::new(from, Rule::<T>::base_rule().end, None, true, None)?;
+        self.add_rule(rule, priority)
+    }
+
+    /// Applies an open or close event from a live feed, making the schedule open or
+    /// closed from `at` until further notice at the given priority.
+    ///
+    /// The state in effect at `at` is ended there, or extended if it already matches, so
+    /// consecutive events result in one rule per state. Absolute rules at the priority
+    /// starting at or after `at` are superseded and removed, so the priority should be
+    /// dedicated to the feed. Clears any generated frames.
+    ///
+    /// In closed exceptions mode, see `closed_exceptions_mode`, an open event only ends
+    /// the closure in effect at `at`, since the schedule is open by default.
+    pub fn apply_event(
+        &mut self,
+        at: NaiveDateTime,
+        open: bool,
+        priority: usize,
+    ) -> Result<(), AvailabilityError> {
+        if priority == 0 {
+            return Err(AvailabilityError::ReservedPriority);
+        }
+        let base_end = Rule::<T>::base_rule().end;
+        let rule = Rule::new(at, base_end, None, !open, None)?;
+        self.frames.clear();
+
+        // An open event adds no rule in closed exceptions mode, see below
+        let reopen_by_default = open && self.closed_exceptions_only;
+        let Some(rules) = self.rules.get_mut(priority) else {
+            if reopen_by_default {
+                return Ok(());
+            }
+            return self.add_rule(rule, priority);
+        };
+        rules.retain(|rule| !rule.is_absolute() || rule.start < at);
+
+        // Coalesce with the state in effect at, or ending right at, `at`
+        for existing in rules.iter_mut().filter(|rule| rule.is_absolute()) {
+            if existing.start < at && at < existing.end {
+                if existing.off == rule.off {
+                    existing.end = base_end;
+                    return Ok(());
+                }
+                existing.end = at;
+            }
+        }
+        if let Some(previous) = rules.iter_mut().find(|existing| {
+            existing.is_absolute() && existing.end == at && existing.off == rule.off
+        }) {
+            previous.end = base_end;
+            return Ok(());
+        }
+        if reopen_by_default {
+            return Ok(());
+        }
+        self.add_rule(rule, priority)
+    }
+
+    /// Adds an "on" rule for `[start, end)` at the given priority.
+    pub fn open_window(
+        &mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        priority: usize,
+    ) -> Result<(), AvailabilityError> {
+        let rule = Rule::new(start, end, None, false, None)?;
+        self.add_rule(rule, priority)
+    }
+
+    /// Adds an "off" rule for `[start, end)` at the given priority.
+    pub fn close_window(
+        &mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        priority: usize,
+    ) -> Result<(), AvailabilityError> {
+        let rule = Rule::new(start, end, None, true, None)?;
+        self.add_rule(rule, priority)
+    }
+
+    /// Adds an "off" rule covering exactly the given frame at the given priority, e.g. to
+    /// block off a frame selected in a UI.
+    pub fn block_frame(
+        &mut self,
+        frame: &Frame<T>,
+        priority: usize,
+    ) -> Result<(), AvailabilityError> {
+        self.close_window(frame.start, frame.end, priority)
+    }
+
     /// Remove rule at specific priority and index
     pub fn remove_rule_by_index(
         &mut self,
         priority: usize,
         rule_index: usize,
-    ) -> Result<Rule<T>, String> {
-        if priority >= self.rules.len() {
-            return Err(format!(
-                "Priority {} does not exist. Max priority is {}.",
-                priority,
-                self.rules.len() - 1
-            ));
-        }
-
+    ) -> Result<Rule<T>, AvailabilityError> {
         if priority == 0 {
-            return Err("Priority 0 is reserved for base rule and cannot be modified".to_string());
+            return Err(AvailabilityError::ReservedPriority);
         }
 
-        if rule_index >= self.rules[priority].len() {
-            return Err(format!(
-                "Rule index {} does not exist at priority level {}.",
-                rule_index, priority
-            ));
+        if rule_index >= self.rules.get(priority).map_or(0, Vec::len) {
+            return Err(AvailabilityError::RuleNotFound {
+                priority,
+                index: rule_index,
+            });
         }
 
         let removed_rule = self.rules[priority].remove(rule_index);
@@ -179,166 +961,578 @@ where
     }
 
     pub fn remove_rule_by_str(&mut self, priority: usize, datetime: &str) -> Option<Rule<T>> {
-        match NaiveDateTime::parse_from_str(datetime, "%y%m%d%H%M%S") {
+        match parse_crate_datetime(datetime) {
             Ok(parsed_datetime) => self.remove_rule_by_datetime(priority, parsed_datetime),
             Err(_) => None,
         }
     }
 
-    /// Converts all added rules into a sequence of non-overlapping, time-sorted frames within the specified range.
+    /// Clips every rule, except the base rule, to `[start, end)` and removes the rules
+    /// left empty, e.g. to narrow down a schedule for archival.
     ///
-    /// This method processes the rules based on their priorities, resolving overlaps by giving precedence
-    /// to higher priority rules. The resulting frames represent distinct time intervals with their corresponding
-    /// availability status and payload.
-    ///
-    /// # Parameters
-    ///
-    /// - `start`: The start datetime of the range to generate frames for. Start is inclusive.
-    /// - `end`: The end datetime of the range to generate frames for. End is exclusive.
-    pub fn to_frames_in_range(&mut self, start: NaiveDateTime, end: NaiveDateTime) {
-        let mut frames: Vec<Frame<T>> = Vec::new();
-
-        // Process rules from highest to lowest priority
-        for priority in (1..self.rules.len()).rev() {
-            let mut priority_frames: Vec<Frame<T>> = Vec::new();
-
-            // Convert all rules at this priority level to absolute rules
-            let mut absolute_rules: Vec<Rule<T>> = Vec::new();
-            for rule in self.rules[priority].iter() {
-                if let Ok(abs_rules) = relative_to_absolute_rules(rule.clone()) {
-                    absolute_rules.extend(abs_rules);
-                }
+    /// Relative rules keep their daily time window, so they are clipped to the whole
+    /// windows within the range. A relative rule left with the window of a single day
+    /// becomes an absolute rule for that window, or is removed if the day is not one of
+    /// its weekdays.
+    pub fn trim_rules_to_range(&mut self, start: NaiveDateTime, end: NaiveDateTime) {
+        for rule in self.rules.iter_mut().skip(1).flatten() {
+            if rule.is_absolute() {
+                rule.start = rule.start.max(start);
+                rule.end = rule.end.min(end);
+                continue;
+            }
+            let mut window_start = start.date().and_time(rule.start.time());
+            if window_start < start {
+                window_start += Duration::days(1);
             }
+            let mut window_end = end.date().and_time(rule.end.time());
+            if window_end > end {
+                window_end -= Duration::days(1);
+            }
+            rule.start = rule.start.max(window_start);
+            rule.end = rule.end.min(window_end);
 
-            // Convert absolute rules to frames
-            for rule in absolute_rules {
-                // Rule is completely within range
-                if rule.start >= start && rule.end < end {
-                    let frame = Frame::new(rule.start, rule.end, rule.off, rule.payload.clone());
-                    priority_frames.push(frame);
-                }
-                // Rule starts before range but ends within range
-                else if rule.start < start && rule.end < end {
-                    let frame = Frame::new(start, rule.end, rule.off, rule.payload.clone());
-                    priority_frames.push(frame);
-                }
-                // Rule starts within range but ends after range
-                else if rule.start >= start && rule.start < end && rule.end >= end {
-                    let frame = Frame::new(rule.start, end, rule.off, rule.payload.clone());
-                    priority_frames.push(frame);
-                }
-                // Rule starts before range and ends after range
-                else if rule.start < start && rule.end >= end {
-                    let frame = Frame::new(start, end, rule.off, rule.payload.clone());
-                    priority_frames.push(frame);
+            // Relative rules within a single day cannot be split into days
+            if rule.start < rule.end && rule.start.date() == rule.end.date() {
+                match rule.is_weekday_enabled(rule.start) {
+                    true => rule.weekdays = None,
+                    // Left empty to be removed below
+                    false => rule.end = rule.start,
                 }
             }
+        }
+        self.retain_rules(|_, rule| rule.start < rule.end);
+    }
 
-            // Merge with existing frames, giving precedence to higher priority frames
-            if frames.is_empty() {
-                frames = priority_frames;
-            } else {
-                let mut merged_frames: Vec<Frame<T>> = Vec::new();
-
-                // Sort both frame lists by start time
-                frames.sort_by(|a, b| a.start.cmp(&b.start));
-                priority_frames.sort_by(|a, b| a.start.cmp(&b.start));
+    /// Moves the rule at `priority` and `index` by `by`, e.g. an hour later, keeping its
+    /// index. Fails without changing anything if the moved rule would overlap another
+    /// rule at the same priority, or with `DuplicateRule` if it would become identical
+    /// to one.
+    ///
+    /// Clears the generated frames.
+    pub fn shift_rule(
+        &mut self,
+        priority: usize,
+        index: usize,
+        by: Duration,
+    ) -> Result<(), AvailabilityError> {
+        if priority == 0 {
+            return Err(AvailabilityError::ReservedPriority);
+        }
+        let Some(rule) = self.rules.get(priority).and_then(|rules| rules.get(index)) else {
+            return Err(AvailabilityError::RuleNotFound { priority, index });
+        };
 
-                let mut i = 0;
-                let mut j = 0;
+        let mut shifted = rule.clone();
+        match (
+            rule.start.checked_add_signed(by),
+            rule.end.checked_add_signed(by),
+        ) {
+            (Some(start), Some(end)) => (shifted.start, shifted.end) = (start, end),
+            _ => {
+                return Err(AvailabilityError::InvalidRule(
+                    "Shifted rule is out of range".to_string(),
+                ))
+            }
+        }
 
-                while i < frames.len() || j < priority_frames.len() {
-                    if i >= frames.len() {
-                        // Add remaining lower priority frames
-                        merged_frames.push(priority_frames[j].clone());
-                        j += 1;
-                        continue;
-                    }
+        // Validate against the other rules at the priority the same way add_rule does
+        let original = self.rules[priority].remove(index);
+        let len = self.rules[priority].len();
+        if let Err(error) = self.add_rule(shifted, priority) {
+            self.rules[priority].insert(index, original);
+            return Err(error);
+        }
+        // `add_rule` accepts an identical rule without adding it, which would lose a rule
+        if self.rules[priority].len() == len {
+            self.rules[priority].insert(index, original);
+            return Err(AvailabilityError::DuplicateRule(priority));
+        }
+        let shifted = self.rules[priority].pop().unwrap();
+        self.rules[priority].insert(index, shifted);
 
-                    if j >= priority_frames.len() {
-                        // Add remaining higher priority frames
-                        merged_frames.push(frames[i].clone());
-                        i += 1;
-                        continue;
-                    }
+        self.frames.clear();
+        Ok(())
+    }
 
-                    let high_frame = &frames[i];
-                    let low_frame = &priority_frames[j];
-
-                    if high_frame.start >= low_frame.end {
-                        // No overlap, add lower priority frame
-                        merged_frames.push(low_frame.clone());
-                        j += 1;
-                    } else if low_frame.start >= high_frame.end {
-                        // No overlap, add higher priority frame
-                        merged_frames.push(high_frame.clone());
-                        i += 1;
-                    } else {
-                        // Overlap exists - keep higher priority frame
-                        if low_frame.start < high_frame.start {
-                            // Add non-overlapping part of lower priority frame
-                            merged_frames.push(Frame::new(
-                                low_frame.start,
-                                high_frame.start,
-                                low_frame.off,
-                                low_frame.payload.clone(),
-                            ));
-                        }
+    /// Replaces the payload of the rule at `priority` and `index`. Unlike removing and
+    /// re-adding the rule, no overlap checks are needed.
+    ///
+    /// Clears the generated frames.
+    pub fn set_payload_at(
+        &mut self,
+        priority: usize,
+        index: usize,
+        payload: Option<T>,
+    ) -> Result<(), AvailabilityError> {
+        if priority == 0 {
+            return Err(AvailabilityError::ReservedPriority);
+        }
+        let Some(rule) = self
+            .rules
+            .get_mut(priority)
+            .and_then(|rules| rules.get_mut(index))
+        else {
+            return Err(AvailabilityError::RuleNotFound { priority, index });
+        };
+
+        rule.payload = payload;
+        self.frames.clear();
+        Ok(())
+    }
 
-                        merged_frames.push(high_frame.clone());
+    /// Resets the schedule in place to a pristine `Availability::new()`.
+    ///
+    /// Removes all custom rules and frames, and also resets the base state (closed), the
+    /// end marker setting, the timezone label, the frame hook and closed exceptions mode.
+    pub fn reset_to_base(&mut self) {
+        *self = Availability::new();
+    }
 
-                        if low_frame.end > high_frame.end {
-                            // Add non-overlapping part of lower priority frame
-                            merged_frames.push(Frame::new(
-                                high_frame.end,
-                                low_frame.end,
-                                low_frame.off,
-                                low_frame.payload.clone(),
-                            ));
-                        }
+    /// Moves all rules at priority `from` to priority `into`, validating each against the
+    /// rules already there like `add_rule`. If any rule conflicts nothing is changed.
+    ///
+    /// Priority `from` is left empty, see `compact_priorities`. Clears any generated frames.
+    pub fn merge_priorities(&mut self, from: usize, into: usize) -> Result<(), AvailabilityError> {
+        if from == 0 || into == 0 {
+            return Err(AvailabilityError::ReservedPriority);
+        }
+        if from == into {
+            return Ok(());
+        }
 
-                        i += 1;
+        // Merge into a scratch copy of the target level so a conflict leaves self untouched
+        let mut merged = Availability::from_snapshot(Snapshot {
+            rules: vec![
+                self.rules[0].clone(),
+                self.rules.get(into).cloned().unwrap_or_default(),
+            ],
+        });
+        for rule in self.rules.get(from).into_iter().flatten() {
+            merged.add_rule(rule.clone(), 1)?;
+        }
 
-                        j += 1;
-                    }
-                }
+        if self.rules.len() <= into {
+            self.rules.resize_with(into + 1, Vec::new);
+        }
+        self.rules[into] = merged.rules.pop().unwrap_or_default();
+        if let Some(rules) = self.rules.get_mut(from) {
+            rules.clear();
+        }
+        self.frames.clear();
+        Ok(())
+    }
+
+    /// Priorities above the base rule that hold no rules, e.g. left behind after removing
+    /// all rules at a priority. See `compact_priorities`.
+    pub fn validate_no_orphan_priorities(&self) -> Vec<usize> {
+        (1..self.rules.len())
+            .filter(|&priority| self.rules[priority].is_empty())
+            .collect()
+    }
+
+    /// Removes priorities above the base rule that hold no rules. Higher priorities move
+    /// down, keeping their relative order, so the generated frames are unaffected.
+    pub fn compact_priorities(&mut self) {
+        let mut priority = 0;
+        self.rules.retain(|rules| {
+            priority += 1;
+            priority == 1 || !rules.is_empty()
+        });
+    }
+
+    /// Removes every rule, except the base rule, for which `pred` returns false. The
+    /// predicate is passed the priority of the rule.
+    ///
+    /// Priority levels left empty at the top are removed afterwards.
+    pub fn retain_rules(&mut self, pred: impl Fn(usize, &Rule<T>) -> bool) {
+        for (priority, rules) in self.rules.iter_mut().enumerate().skip(1) {
+            rules.retain(|rule| pred(priority, rule));
+        }
+
+        while self.rules.len() > 1 && self.rules.last().is_some_and(|rules| rules.is_empty()) {
+            self.rules.pop();
+        }
+    }
+
+    /// Converts all added rules into a sequence of non-overlapping, time-sorted frames within the specified range.
+    ///
+    /// This method processes the rules based on their priorities, resolving overlaps by giving precedence
+    /// to higher priority rules. The resulting frames represent distinct time intervals with their corresponding
+    /// availability status and payload.
+    ///
+    /// # Parameters
+    ///
+    /// - `start`: The start datetime of the range to generate frames for. Start is inclusive.
+    /// - `end`: The end datetime of the range to generate frames for. End is exclusive.
+    pub fn to_frames_in_range(&mut self, start: NaiveDateTime, end: NaiveDateTime) {
+        let frames = self.compute_frames(start, end);
+        self.store_frames(frames);
+    }
+
+    /// Like `to_frames_in_range`, but fails if more than `max` frames would be generated.
+    ///
+    /// Generation stops as soon as the limit is exceeded, so a dense or long rule set costs
+    /// no more than the frames allowed. On error the previously generated frames are left
+    /// untouched.
+    pub fn to_frames_in_range_capped(
+        &mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        max: usize,
+    ) -> Result<(), AvailabilityError> {
+        let frames = self.sweep_frames(
+            start,
+            end,
+            &|_, _| false,
+            self.all_rules_absolute(),
+            false,
+            Some(max),
+        )?;
+        self.store_frames(frames);
+        Ok(())
+    }
 
-                frames = merged_frames;
+    /// Runs the frame hook on each frame and stores them as the generated frames.
+    fn store_frames(&mut self, mut frames: Vec<Frame<T>>) {
+        if self.include_end_marker {
+            if let Some(end) = frames.last().map(|frame| frame.end) {
+                frames.push(Frame::end_marker(end, !self.is_open(end)));
             }
         }
 
-        // Sort final frames by start time
-        frames.sort_by(|a, b| a.start.cmp(&b.start));
+        if let Some(hook) = &self.frame_hook {
+            for frame in frames.iter() {
+                hook(frame);
+            }
+        }
+
+        self.frames = frames;
+    }
+
+    /// Like `to_frames_in_range`, but merges adjacent frames for which `eq` returns true
+    /// while the frames are generated. A merged frame keeps the status and payload of the
+    /// first frame.
+    pub fn to_frames_in_range_with_eq(
+        &mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        eq: impl Fn(&Frame<T>, &Frame<T>) -> bool,
+    ) {
+        let frames = self.resolve_frames(start, end, &eq);
+        self.store_frames(frames);
+    }
+
+    /// Resolves the rules within `[start, end)` and returns only the open frames, without
+    /// storing them. Higher priority off rules are still subtracted.
+    ///
+    /// Cheaper than filtering the full frames: no off frames or closed gaps are built, and
+    /// when the base is off, priorities below the lowest one holding an "on" rule are not
+    /// resolved at all, since they can only close what is already closed.
+    pub fn compute_open_frames(&self, start: NaiveDateTime, end: NaiveDateTime) -> Vec<Frame<T>> {
+        // Without a frame limit the sweep cannot fail
+        self.sweep_frames(
+            start,
+            end,
+            &|_, _| false,
+            self.all_rules_absolute(),
+            true,
+            None,
+        )
+        .unwrap()
+    }
+
+    /// Computes the frames within `[start, end)` that are open in this schedule but closed
+    /// in `booked`, i.e. the free time left after subtracting the booked (open) time.
+    ///
+    /// The returned frames keep the payloads of this schedule. Nothing is stored.
+    pub fn subtract(
+        &self,
+        booked: &Availability<T>,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<Frame<T>> {
+        let open = self.compute_open_frames(start, end);
+        let free: Vec<Frame<T>> = booked
+            .compute_frames(start, end)
+            .into_iter()
+            .filter(|frame| frame.is_off())
+            .collect();
 
-        // Insert base rule if first frame from coustom rules is not at start
-        if !frames.is_empty() && frames[0].start > start {
-            frames.insert(0, Frame::new(start, frames[0].start, true, None));
+        // Both lists are sorted and non-overlapping, so a single pass intersects them
+        let mut frames = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < open.len() && j < free.len() {
+            let overlap_start = open[i].start.max(free[j].start);
+            let overlap_end = open[i].end.min(free[j].end);
+            if overlap_start < overlap_end {
+                frames.push(Frame::new(
+                    overlap_start,
+                    overlap_end,
+                    false,
+                    open[i].payload.clone(),
+                ));
+            }
+            if open[i].end <= free[j].end {
+                i += 1;
+            } else {
+                j += 1;
+            }
         }
+        frames
+    }
+
+    /// Computes the frames within `[start, end)` as they would be after adding `rule` at
+    /// `priority`, without modifying this schedule.
+    ///
+    /// Fails like `add_rule` would.
+    pub fn preview_add(
+        &self,
+        rule: Rule<T>,
+        priority: usize,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<Frame<T>>, AvailabilityError> {
+        // A clone keeps the settings `add_rule` depends on, like closed exceptions mode
+        let mut preview = self.clone();
+        preview.add_rule(rule, priority)?;
+        Ok(preview.compute_frames(start, end))
+    }
+
+    /// True if adding `rule` at `priority` would change any frame within `[start, end)`,
+    /// e.g. to warn that a rule fully shadowed by higher priorities has no effect.
+    ///
+    /// A rule that `add_rule` would reject changes nothing and returns `false`.
+    pub fn add_changes_frames(
+        &self,
+        rule: Rule<T>,
+        priority: usize,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> bool {
+        let Ok(after) = self.preview_add(rule, priority, start, end) else {
+            return false;
+        };
+        let before = self.compute_frames(start, end);
+        before.len() != after.len()
+            || before
+                .iter()
+                .zip(after.iter())
+                .any(|(before, after)| !before.approx_eq(after, Duration::zero()))
+    }
+
+    /// Computes the frames within `[start, end)` where any of the schedules is open, e.g.
+    /// when any provider in a pool is available.
+    ///
+    /// Each frame keeps the payload of the first schedule open during it.
+    pub fn any_open(
+        schedules: &[&Availability<T>],
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<Frame<T>> {
+        combine_open(schedules, start, end, |frames| {
+            frames
+                .iter()
+                .find(|frame| frame.is_on())
+                .map(|frame| frame.payload.clone())
+        })
+    }
+
+    /// Computes the frames within `[start, end)` where all of the schedules are open, e.g.
+    /// when every provider is available.
+    ///
+    /// Each frame keeps the payload of the first schedule.
+    pub fn all_open(
+        schedules: &[&Availability<T>],
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<Frame<T>> {
+        combine_open(schedules, start, end, |frames| {
+            match frames.iter().all(|frame| frame.is_on()) {
+                true => Some(frames[0].payload.clone()),
+                false => None,
+            }
+        })
+    }
+
+    /// Resolves the rules into frames within `[start, end)` without storing them.
+    pub(crate) fn compute_frames(&self, start: NaiveDateTime, end: NaiveDateTime) -> Vec<Frame<T>> {
+        self.resolve_frames(start, end, &|_, _| false)
+    }
+
+    /// Resolves the rules into frames within `[start, end)`, merging adjacent frames for
+    /// which `eq` returns true.
+    fn resolve_frames(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        eq: &dyn Fn(&Frame<T>, &Frame<T>) -> bool,
+    ) -> Vec<Frame<T>> {
+        // Without a frame limit the sweep cannot fail
+        self.sweep_frames(start, end, eq, self.all_rules_absolute(), false, None)
+            .unwrap()
+    }
+
+    /// True if there are no relative rules, so there is nothing to expand and the rules
+    /// can be swept as they are.
+    fn all_rules_absolute(&self) -> bool {
+        self.rules
+            .iter()
+            .skip(1)
+            .flatten()
+            .all(|rule| rule.is_absolute())
+    }
+
+    /// Sweeps the rules into frames, see `resolve_frames`. With `all_absolute` set the
+    /// rules are used directly instead of being expanded, which requires all of them to
+    /// be absolute. With `open_only` set only the open frames are returned, see
+    /// `compute_open_frames`. With `max_frames` set the sweep stops with
+    /// `FrameLimitExceeded` as soon as more frames than that are resolved, which assumes
+    /// `eq` merges nothing.
+    fn sweep_frames(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        eq: &dyn Fn(&Frame<T>, &Frame<T>) -> bool,
+        all_absolute: bool,
+        open_only: bool,
+        max_frames: Option<usize>,
+    ) -> Result<Vec<Frame<T>>, AvailabilityError> {
+        // Frames resolved so far, keyed and thereby sorted by start
+        let mut covered: BTreeMap<NaiveDateTime, Frame<T>> = BTreeMap::new();
+
+        // When closed by default, off rules below every on rule cannot change the result
+        let base_off = self.is_base_off();
+        let lowest_priority = match open_only && base_off {
+            true => (1..self.rules.len())
+                .find(|&priority| self.rules[priority].iter().any(|rule| !rule.off))
+                .unwrap_or(self.rules.len()),
+            false => 1,
+        };
+
+        // Only keep the parts of a rule not already covered by higher priority frames,
+        // clipped to the range. A rule ending exactly at the (exclusive) end of the range
+        // is not clipped at all. Rules at the same priority should not overlap, but if
+        // they do the first one wins.
+        let mut cover = |rule: &Rule<T>| {
+            // Rule is completely outside range
+            if rule.end <= start || rule.start >= end {
+                return Ok(());
+            }
+            // Off frames that are not returned only need to mask lower priorities
+            let payload = match open_only && rule.off {
+                true => &None,
+                false => &rule.payload,
+            };
+            for (part_start, part_end) in
+                uncovered_parts(&covered, rule.start.max(start), rule.end.min(end))
+            {
+                covered.insert(
+                    part_start,
+                    Frame::new(part_start, part_end, rule.off, payload.clone()),
+                );
+            }
+            match max_frames {
+                Some(max) if covered.len() > max => Err(AvailabilityError::FrameLimitExceeded {
+                    max,
+                    actual: covered.len(),
+                }),
+                _ => Ok(()),
+            }
+        };
 
-        // Fill gaps in custom rules with base rule
-        let mut i = 0;
-        while i + 1 < frames.len() {
-            let this_end = frames[i].end;
-            let next_start = frames[i + 1].start;
-            if this_end < next_start {
-                // Gap from [this_end, next_start)
-                frames.insert(i + 1, Frame::new(this_end, next_start, true, None));
+        // Process rules from highest to lowest priority
+        for priority in (lowest_priority..self.rules.len()).rev() {
+            for rule in self.rules[priority].iter() {
+                if all_absolute {
+                    cover(rule)?;
+                    continue;
+                }
+                // Only expand the days of relative rules that can overlap the range, one
+                // at a time so a frame limit stops the expansion too
+                if let Ok(absolute_rules) = iter_absolute_rules_in_range(rule.clone(), start, end) {
+                    for absolute_rule in absolute_rules.flatten() {
+                        cover(&absolute_rule)?;
+                    }
+                }
             }
-            i += 1;
         }
 
-        // Fill trailing gap with base rule
-        if let Some(last_frame) = frames.last() {
-            if last_frame.end < end {
-                frames.push(Frame::new(last_frame.end, end, true, None));
+        // Fill leading, inner and trailing gaps in custom rules with base rule, merging
+        // adjacent frames the comparator considers equivalent
+        let mut resolved: Vec<Frame<T>> = Vec::with_capacity(covered.len() * 2 + 1);
+        let mut push = |frame: Frame<T>| {
+            if open_only && frame.off {
+                return;
             }
+            match resolved.last_mut() {
+                Some(last) if last.end == frame.start && eq(last, &frame) => last.end = frame.end,
+                _ => resolved.push(frame),
+            }
+        };
+        let mut cursor = start;
+        for frame in covered.into_values() {
+            if cursor < frame.start {
+                // Gap from [cursor, frame.start)
+                push(Frame::new(cursor, frame.start, base_off, None));
+            }
+            cursor = frame.end;
+            push(frame);
         }
-        // If no frames at all were built, create one that covers [start, end) with the base rule
-        if frames.is_empty() {
-            frames.push(Frame::new(start, end, true, None));
+        // This also covers the case where no frames at all were built
+        if cursor < end {
+            push(Frame::new(cursor, end, base_off, None));
         }
 
-        self.frames = frames;
+        if let Some(max) = max_frames.filter(|&max| resolved.len() > max) {
+            return Err(AvailabilityError::FrameLimitExceeded {
+                max,
+                actual: resolved.len(),
+            });
+        }
+        if !open_only {
+            debug_assert_frames_invariant(&resolved, start, end);
+        }
+        Ok(resolved)
+    }
+
+    /// Resolves the rules into frames within `[start, end)`, where all datetimes are in UTC.
+    ///
+    /// Each rule is interpreted in its own timezone, or UTC if it has none. Relative rules
+    /// are split into days in their local time before conversion, so daily windows follow
+    /// daylight saving time. The frames are returned and not stored.
+    #[cfg(feature = "tz")]
+    pub fn to_utc_frames_in_range(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<Frame<T>> {
+        let mut zoned = Availability::from_snapshot(Snapshot {
+            rules: vec![self.rules[0].clone()],
+        });
+        // Widen the window by a day on either side to cover any timezone offset
+        let (local_start, local_end) = (start - Duration::days(1), end + Duration::days(1));
+        for rules in self.rules.iter().skip(1) {
+            let mut utc_rules = Vec::new();
+            for rule in rules.iter() {
+                let Ok(absolute_rules) =
+                    relative_to_absolute_rules_in_range(rule.clone(), local_start, local_end)
+                else {
+                    continue;
+                };
+                for mut absolute_rule in absolute_rules {
+                    if let Some(tz) = absolute_rule.tz.take() {
+                        absolute_rule.start = local_to_utc(tz, absolute_rule.start);
+                        absolute_rule.end = local_to_utc(tz, absolute_rule.end);
+                    }
+                    if absolute_rule.start < absolute_rule.end {
+                        utc_rules.push(absolute_rule);
+                    }
+                }
+            }
+            zoned.rules.push(utc_rules);
+        }
+        zoned.compute_frames(start, end)
     }
 
     /// Converts all added rules into frames within the specified range using datetime strings.
@@ -353,14 +1547,238 @@ where
     /// - `start_str`: A string slice representing the start datetime in `"YYMMDDHHMMSS"` format. Start is inclusive.
     /// - `end_str`: A string slice representing the end datetime in `"YYMMDDHHMMSS"` format. End is exclusive.
     pub fn to_frames_in_range_str(&mut self, start: &str, end: &str) {
-        if let (Ok(parsed_start), Ok(parsed_end)) = (
-            NaiveDateTime::parse_from_str(start, "%y%m%d%H%M%S"),
-            NaiveDateTime::parse_from_str(end, "%y%m%d%H%M%S"),
-        ) {
+        if let (Ok(parsed_start), Ok(parsed_end)) =
+            (parse_crate_datetime(start), parse_crate_datetime(end))
+        {
             self.to_frames_in_range(parsed_start, parsed_end)
         }
     }
 
+    /// True if the schedule is open at the given datetime.
+    ///
+    /// Evaluates the rules directly, highest priority first, without generating frames.
+    pub fn is_open(&self, datetime: NaiveDateTime) -> bool {
+        for priority in (0..self.rules.len()).rev() {
+            if let Some(rule) = self.rules[priority]
+                .iter()
+                .find(|rule| rule.applies_at(datetime))
+            {
+                return !rule.off;
+            }
+        }
+        false
+    }
+
+    /// Priority and index of the rule that decides the state at the given datetime, the
+    /// same way `is_open` does. Returns `None` if only the base rule applies.
+    pub fn rule_at(&self, datetime: NaiveDateTime) -> Option<(usize, usize)> {
+        (1..self.rules.len()).rev().find_map(|priority| {
+            self.rules[priority]
+                .iter()
+                .position(|rule| rule.applies_at(datetime))
+                .map(|index| (priority, index))
+        })
+    }
+
+    /// True if the schedule is open at the given time of day on the given weekday, e.g. for
+    /// previewing a weekly template. Only relative rules are evaluated, highest priority
+    /// first, ignoring their date range.
+    ///
+    /// Returns `None` if no relative rule matches and custom absolute rules, which need a
+    /// date, could decide instead. Otherwise falls back to the base state.
+    pub fn is_open_weekly(&self, weekday: Weekday, time: NaiveTime) -> Option<bool> {
+        let weekday = Weekdays::from_chrono_weekday(weekday);
+        for priority in (1..self.rules.len()).rev() {
+            let matched = self.rules[priority].iter().find(|rule| {
+                !rule.is_absolute()
+                    && rule
+                        .weekdays
+                        .is_some_and(|weekdays| weekdays.intersects(weekday))
+                    && rule.is_time_within(time)
+            });
+            if let Some(rule) = matched {
+                return Some(!rule.off);
+            }
+        }
+
+        let has_absolute_rules = self
+            .iter_rules()
+            .any(|(priority, rule)| priority > 0 && rule.is_absolute());
+        (!has_absolute_rules).then(|| !self.is_base_off())
+    }
+
+    /// Payload of the rule that decides the state at the given datetime, see `rule_at`, or
+    /// `default` if that rule has no payload or only the base rule applies.
+    pub fn at_or_default(&self, datetime: NaiveDateTime, default: T) -> T {
+        self.rule_at(datetime)
+            .and_then(|(priority, index)| self.rules[priority][index].payload.clone())
+            .unwrap_or(default)
+    }
+
+    /// Explains why the schedule is open or closed at the given datetime, for debugging.
+    ///
+    /// Lists the priority levels from highest to lowest with the rule that matched at
+    /// each and which one decided the state, the same way `is_open` does. E.g.
+    /// `"priority 3 off-rule matched (wins); priority 1 on-rule matched but lower priority; base closed."`
+    pub fn explain(&self, datetime: NaiveDateTime) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        let mut decided = false;
+        for priority in (0..self.rules.len()).rev() {
+            let matched = self.rules[priority]
+                .iter()
+                .find(|rule| rule.applies_at(datetime));
+            let mut part = match (priority, matched) {
+                (0, Some(rule)) => format!("base {}", if rule.off { "closed" } else { "open" }),
+                (0, None) => "base did not match".to_string(),
+                (_, Some(rule)) => format!(
+                    "priority {} {}-rule matched",
+                    priority,
+                    if rule.off { "off" } else { "on" }
+                ),
+                (_, None) if self.rules[priority].is_empty() => continue,
+                (_, None) => format!("priority {} no rule matched", priority),
+            };
+            if matched.is_some() {
+                if !decided {
+                    part.push_str(" (wins)");
+                    decided = true;
+                } else if priority != 0 {
+                    part.push_str(" but lower priority");
+                }
+            }
+            parts.push(part);
+        }
+        if !decided {
+            parts.push("closed by default".to_string());
+        }
+        parts.join("; ") + "."
+    }
+
+    /// True if the schedule is open at the given datetime string, which must be in the
+    /// `"YYMMDDHHMMSS"` format like for the other `_str` methods. Returns `None` if the
+    /// string cannot be parsed.
+    pub fn is_open_str(&self, datetime: &str) -> Option<bool> {
+        parse_crate_datetime(datetime)
+            .ok()
+            .map(|parsed_datetime| self.is_open(parsed_datetime))
+    }
+
+    /// True if the schedule is open at the given RFC 3339 datetime string, e.g.
+    /// `"2024-01-01T09:00:00"`. See `parse_iso_datetime` for the accepted formats.
+    pub fn is_open_iso(&self, datetime: &str) -> Result<bool, AvailabilityError> {
+        parse_iso_datetime(datetime).map(|parsed_datetime| self.is_open(parsed_datetime))
+    }
+
+    /// The next frame boundary after `after` within the generated frames: the end of the
+    /// frame containing `after`, or the start of the first frame after it. Returns `None`
+    /// past the last generated frame. Unlike `next_open` and `next_closed`, only the
+    /// stored frames are consulted, so call `to_frames_in_range` first.
+    pub fn next_transition(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.frames
+            .iter()
+            .find(|frame| frame.end > after)
+            .map(|frame| {
+                if frame.start > after {
+                    frame.start
+                } else {
+                    frame.end
+                }
+            })
+    }
+
+    /// Start of the next open frame at or after `from`, or `None` if the schedule
+    /// stays closed until the end of the base rule.
+    ///
+    /// Evaluates the rules rather than the generated frames, so it works past the last
+    /// generated frame too, see `next_transition` for the generated frames. The search
+    /// ends where the last custom rule ends.
+    pub fn next_open(&self, from: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.next_state_change(from, false)
+    }
+
+    /// Start of the next closed frame at or after `from`, or `None` if the schedule
+    /// stays open until the end of the base rule.
+    ///
+    /// Evaluates the rules like `next_open`, not the generated frames.
+    pub fn next_closed(&self, from: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.next_state_change(from, true)
+    }
+
+    /// Start of the first frame at or after `from` whose `off` state is `off`. Frames are
+    /// resolved in windows doubling in size, so nearby boundaries are found quickly.
+    ///
+    /// Only the base rule applies after the last custom rule ends, so frames are never
+    /// resolved past that point, and not at all if no custom rule ends after `from`.
+    fn next_state_change(&self, from: NaiveDateTime, off: bool) -> Option<NaiveDateTime> {
+        let base_end = Rule::<T>::base_rule().end;
+        if from >= base_end {
+            return None;
+        }
+        let base_state_at = |at: NaiveDateTime| (self.is_base_off() == off).then_some(at);
+        let Some(last_end) = self
+            .iter_rules()
+            .filter(|(priority, rule)| *priority > 0 && rule.end > from)
+            .map(|(_, rule)| rule.end)
+            .max()
+        else {
+            return base_state_at(from);
+        };
+
+        let mut window = Duration::weeks(1);
+        loop {
+            let end = from
+                .checked_add_signed(window)
+                .map_or(last_end, |end| end.min(last_end));
+            let frames = self.compute_frames(from, end);
+            if let Some(frame) = frames.iter().find(|frame| frame.off == off) {
+                return Some(frame.start);
+            }
+            if end == last_end {
+                return base_state_at(last_end).filter(|&at| at < base_end);
+            }
+            window = window * 2;
+        }
+    }
+
+    /// True if the schedule is open during the entire interval `[from, to)`, i.e. no
+    /// closed frame intersects it. Empty or inverted intervals are never open.
+    pub fn is_open_range(&self, from: NaiveDateTime, to: NaiveDateTime) -> bool {
+        from < to
+            && self
+                .compute_frames(from, to)
+                .iter()
+                .all(|frame| frame.is_on())
+    }
+
+    /// True if the schedule is open during every one of the intervals, e.g. to validate a
+    /// batch of requested booking slots. Like `is_open_range` for each interval, but the
+    /// frames are only resolved once. An empty list is trivially open.
+    pub fn is_open_all_of(&self, intervals: &[(NaiveDateTime, NaiveDateTime)]) -> bool {
+        if intervals.iter().any(|(from, to)| from >= to) {
+            return false;
+        }
+        let mut intervals = intervals.to_vec();
+        intervals.sort();
+        let (Some(&(start, _)), Some(end)) =
+            (intervals.first(), intervals.iter().map(|&(_, to)| to).max())
+        else {
+            return true;
+        };
+
+        let closed = sessions(&self.compute_frames(start, end), true);
+        let mut closed = closed.iter().peekable();
+        intervals.iter().all(|&(from, to)| {
+            // Intervals are sorted by start, so closures ending before one never matter again
+            while closed
+                .next_if(|(_, closed_end)| *closed_end <= from)
+                .is_some()
+            {}
+            closed
+                .peek()
+                .is_none_or(|(closed_start, _)| *closed_start >= to)
+        })
+    }
+
     pub fn get_frame(&self, datetime: NaiveDateTime) -> Option<Frame<T>> {
         let mut current_frame: Option<Frame<T>> = None;
         for frame in self.frames.iter() {
@@ -374,62 +1792,784 @@ where
     /// Retrieves the frame corresponding to the specified datetime string.
     /// The datetime string must be in the `"YYMMDDHHMMSS"` format.
     pub fn get_frame_from_str(&self, datetime: &str) -> Option<Frame<T>> {
-        match NaiveDateTime::parse_from_str(datetime, "%y%m%d%H%M%S") {
+        match parse_crate_datetime(datetime) {
+            Ok(parsed_datetime) => self.get_frame(parsed_datetime),
+            Err(_) => None,
+        }
+    }
+
+    /// Retrieves the frame corresponding to the specified RFC 3339 datetime string, e.g.
+    /// `"2024-01-01T09:00:00"`. See `parse_iso_datetime` for the accepted formats.
+    pub fn get_frame_iso(&self, datetime: &str) -> Option<Frame<T>> {
+        match parse_iso_datetime(datetime) {
             Ok(parsed_datetime) => self.get_frame(parsed_datetime),
             Err(_) => None,
         }
     }
 
+    /// The generated open frames intersecting `[start, end)`, clipped to it.
+    pub fn open_frames_between(&self, start: NaiveDateTime, end: NaiveDateTime) -> Vec<Frame<T>> {
+        self.frames
+            .iter()
+            .filter(|frame| frame.is_on() && frame.start < end && frame.end > start)
+            .map(|frame| {
+                Frame::new(
+                    frame.start.max(start),
+                    frame.end.min(end),
+                    frame.off,
+                    frame.payload.clone(),
+                )
+            })
+            .collect()
+    }
+
     /// Retrieves all generated frames.
     pub fn frames(&self) -> &Vec<Frame<T>> {
         &self.frames
     }
 
-    /// Clears all generated frames.
-    pub fn clear_frames(&mut self) {
-        self.frames.clear();
+    /// Iterates over the generated frames in start time order, like `frames` but without
+    /// exposing the container. `&Availability` also implements `IntoIterator`, so
+    /// `for frame in &availability` works the same way.
+    pub fn iter_frames(&self) -> impl Iterator<Item = &Frame<T>> {
+        self.frames.iter()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{
-        rulebuilder::RuleBuilder,
-        weekdays::{FRIDAY, MONDAY, THURSDAY, TUESDAY, WEDNESDAY},
-    };
+    /// Exports the generated frames as parallel columns, e.g. for loading into a dataframe.
+    ///
+    /// Payloads that fail to serialize are exported as `None`.
+    #[cfg(feature = "columnar")]
+    pub fn to_columns(&self) -> FrameColumns {
+        let mut starts = Vec::with_capacity(self.frames.len());
+        let mut ends = Vec::with_capacity(self.frames.len());
+        let mut offs = Vec::with_capacity(self.frames.len());
+        let mut payloads = Vec::with_capacity(self.frames.len());
+        for frame in self.frames.iter() {
+            starts.push(frame.start);
+            ends.push(frame.end);
+            offs.push(frame.off);
+            payloads.push(
+                frame
+                    .payload
+                    .as_ref()
+                    .and_then(|payload| serde_json::to_value(payload).ok()),
+            );
+        }
+        (starts, ends, offs, payloads)
+    }
 
-    use super::*;
-    use chrono::{NaiveDate, NaiveDateTime};
-    use serde_json::{json, Value};
+    /// Merges adjacent "off" frames in the generated frames, e.g. a base rule gap next to
+    /// an off rule.
+    ///
+    /// When only one of the frames has a payload it is kept. Off frames with differing
+    /// payloads are not merged.
+    pub fn merge_adjacent_off_gaps(&mut self) {
+        let mut merged: Vec<Frame<T>> = Vec::with_capacity(self.frames.len());
+        for frame in self.frames.drain(..) {
+            if let Some(last) = merged.last_mut() {
+                if last.off && frame.off && last.end == frame.start && !frame.is_end_marker() {
+                    let payloads_compatible = match (&last.payload, &frame.payload) {
+                        (Some(a), Some(b)) => {
+                            serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+                        }
+                        _ => true,
+                    };
+                    if payloads_compatible {
+                        last.end = frame.end;
+                        if last.payload.is_none() {
+                            last.payload = frame.payload;
+                        }
+                        continue;
+                    }
+                }
+            }
+            merged.push(frame);
+        }
+        self.frames = merged;
+    }
 
-    fn create_datetime(
-        year: i32,
-        month: u32,
-        day: u32,
-        hour: u32,
-        min: u32,
-        sec: u32,
-    ) -> NaiveDateTime {
-        NaiveDate::from_ymd_opt(year, month, day)
-            .unwrap()
-            .and_hms_opt(hour, min, sec)
-            .unwrap()
+    /// Groups consecutive generated frames with the same `off` state into runs, e.g. for
+    /// rendering. Unlike merging, the frames inside a run are kept as they are.
+    pub fn runs(&self) -> Vec<(bool, Vec<Frame<T>>)> {
+        let mut runs: Vec<(bool, Vec<Frame<T>>)> = Vec::new();
+        for frame in self.frames.iter() {
+            match runs.last_mut() {
+                Some((off, frames)) if *off == frame.off => frames.push(frame.clone()),
+                _ => runs.push((frame.off, vec![frame.clone()])),
+            }
+        }
+        runs
     }
 
-    #[test]
-    fn test_new_empty() {
-        let availability: Availability<Value> = Availability::new();
-        assert_eq!(availability.rules.len(), 1); // Should have base rule
-        assert_eq!(availability.frames.len(), 0); // No frames yet
+    /// Compact summary of the weekly opening hours, e.g. `"Mon-Fri 9–5, Sat 10–2, Sun closed"`.
+    ///
+    /// Returns `None` unless the rules form a clean repeating week, i.e. all rules are
+    /// relative "on" rules over the same dates with at most one rule per weekday.
+    pub fn weekly_summary(&self) -> Option<String> {
+        const DAYS: [(Weekdays, &str); 7] = [
+            (Weekdays::MONDAY, "Mon"),
+            (Weekdays::TUESDAY, "Tue"),
+            (Weekdays::WEDNESDAY, "Wed"),
+            (Weekdays::THURSDAY, "Thu"),
+            (Weekdays::FRIDAY, "Fri"),
+            (Weekdays::SATURDAY, "Sat"),
+            (Weekdays::SUNDAY, "Sun"),
+        ];
 
-        // Check base rule properties
-        let base_rule = &availability.rules[0][0];
-        assert!(base_rule.off);
-        assert!(base_rule.is_absolute());
-        assert!(base_rule.payload.is_none());
+        let rules: Vec<&Rule<T>> = self.rules.iter().skip(1).flatten().collect();
+        let first = rules.first()?;
+        let mut windows: [Option<(NaiveTime, NaiveTime)>; 7] = [None; 7];
+        for rule in rules.iter() {
+            if rule.off
+                || rule.is_absolute()
+                || rule.start.date() != first.start.date()
+                || rule.end.date() != first.end.date()
+            {
+                return None;
+            }
+            for (day, (weekday, _)) in DAYS.iter().enumerate() {
+                if rule
+                    .weekdays
+                    .is_some_and(|weekdays| weekdays.intersects(*weekday))
+                {
+                    if windows[day].is_some() {
+                        return None;
+                    }
+                    windows[day] = Some((rule.start.time(), rule.end.time()));
+                }
+            }
+        }
+
+        // Group consecutive days with the same hours
+        let mut parts: Vec<String> = Vec::new();
+        let mut day = 0;
+        while day < DAYS.len() {
+            let mut last = day;
+            while last + 1 < DAYS.len() && windows[last + 1] == windows[day] {
+                last += 1;
+            }
+            let days = match last == day {
+                true => DAYS[day].1.to_string(),
+                false => format!("{}-{}", DAYS[day].1, DAYS[last].1),
+            };
+            let hours = match windows[day] {
+                Some((start, end)) => format!("{}–{}", short_time(start), short_time(end)),
+                None => "closed".to_string(),
+            };
+            parts.push(format!("{} {}", days, hours));
+            day = last + 1;
+        }
+        Some(parts.join(", "))
     }
 
-    #[test]
+    /// Merges adjacent open frames split at midnight, e.g. the per-day frames of a
+    /// relative rule open around the clock, when their payloads match.
+    pub fn coalesce_across_midnight(&mut self) {
+        let mut merged: Vec<Frame<T>> = Vec::with_capacity(self.frames.len());
+        for frame in self.frames.drain(..) {
+            if let Some(last) = merged.last_mut() {
+                if last.is_on()
+                    && frame.is_on()
+                    && !frame.is_end_marker()
+                    && last.end == frame.start
+                    && frame.start.time() == NaiveTime::MIN
+                    && serde_json::to_value(&last.payload).ok()
+                        == serde_json::to_value(&frame.payload).ok()
+                {
+                    last.end = frame.end;
+                    continue;
+                }
+            }
+            merged.push(frame);
+        }
+        self.frames = merged;
+    }
+
+    /// The distinct payloads of the generated frames in order of first appearance, e.g.
+    /// for a legend. Payloads are compared by their JSON representation.
+    pub fn distinct_payloads(&self) -> Vec<T> {
+        let mut seen: Vec<serde_json::Value> = Vec::new();
+        let mut payloads: Vec<T> = Vec::new();
+        for payload in self
+            .frames
+            .iter()
+            .filter_map(|frame| frame.payload.as_ref())
+        {
+            let Ok(value) = serde_json::to_value(payload) else {
+                continue;
+            };
+            if !seen.contains(&value) {
+                seen.push(value);
+                payloads.push(payload.clone());
+            }
+        }
+        payloads
+    }
+
+    /// Clears all generated frames.
+    pub fn clear_frames(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Fraction of the given weekday that is open, aggregated over every such weekday
+    /// within `[start, end)`.
+    ///
+    /// Partial days at the edges of the range only count the part inside the range.
+    /// Returns `0.0` if the range contains no time on the given weekday.
+    pub fn open_ratio_for_weekday(
+        &self,
+        weekday: Weekday,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> f64 {
+        if start >= end {
+            return 0.0;
+        }
+        let frames = self.compute_frames(start, end);
+
+        let mut total = Duration::zero();
+        let mut open = Duration::zero();
+        let mut day = start.date();
+        while day.and_hms_opt(0, 0, 0).unwrap() < end {
+            if day.weekday() == weekday {
+                let day_start = day.and_hms_opt(0, 0, 0).unwrap().max(start);
+                let day_end = match day.succ_opt() {
+                    Some(next) => next.and_hms_opt(0, 0, 0).unwrap().min(end),
+                    None => end,
+                };
+                total += day_end - day_start;
+                for frame in frames.iter().filter(|frame| frame.is_on()) {
+                    open += frame.overlap_with(day_start, day_end);
+                }
+            }
+            match day.succ_opt() {
+                Some(next) => day = next,
+                None => break,
+            }
+        }
+
+        if total.is_zero() {
+            return 0.0;
+        }
+        open.num_seconds() as f64 / total.num_seconds() as f64
+    }
+
+    /// Number of days within `[start, end)` that are open at some point.
+    pub fn open_day_count(&self, start: NaiveDate, end: NaiveDate) -> usize {
+        if start >= end {
+            return 0;
+        }
+        let open_frames = self.compute_open_frames(
+            start.and_hms_opt(0, 0, 0).unwrap(),
+            end.and_hms_opt(0, 0, 0).unwrap(),
+        );
+        start
+            .iter_days()
+            .take_while(|day| *day < end)
+            .filter(|day| {
+                let day_start = day.and_hms_opt(0, 0, 0).unwrap();
+                let day_end = day_start + Duration::days(1);
+                open_frames
+                    .iter()
+                    .any(|frame| !frame.overlap_with(day_start, day_end).is_zero())
+            })
+            .count()
+    }
+
+    /// Total open time on the given calendar day, in seconds.
+    pub fn open_seconds_on(&self, date: NaiveDate) -> i64 {
+        let day_start = date.and_hms_opt(0, 0, 0).unwrap();
+        self.compute_open_frames(day_start, day_start + Duration::days(1))
+            .iter()
+            .map(|frame| frame.duration().num_seconds())
+            .sum()
+    }
+
+    /// Total open time over the generated frames.
+    pub fn total_open_duration(&self) -> Duration {
+        self.frames
+            .iter()
+            .filter(|frame| frame.is_on())
+            .map(|frame| frame.duration())
+            .sum()
+    }
+
+    /// Total closed time over the generated frames.
+    pub fn total_closed_duration(&self) -> Duration {
+        self.frames
+            .iter()
+            .filter(|frame| frame.is_off())
+            .map(|frame| frame.duration())
+            .sum()
+    }
+
+    /// Total open time of the generated frames within `[start, end)`. Frames are clipped
+    /// to the interval, so it need not match the range the frames were generated for.
+    pub fn open_duration_in(&self, start: NaiveDateTime, end: NaiveDateTime) -> Duration {
+        self.open_frames_between(start, end)
+            .iter()
+            .map(|frame| frame.duration())
+            .sum()
+    }
+
+    /// Total open time within `[start, end)` on business days, i.e. Monday to Friday.
+    /// Open time on weekends is excluded regardless of the rules.
+    pub fn open_duration_business_days(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Duration {
+        let mut open = Duration::zero();
+        for frame in self.compute_open_frames(start, end) {
+            // Split the frame at midnight so each part falls on a single day
+            let mut cursor = frame.start;
+            while cursor < frame.end {
+                let next_day = match cursor.date().succ_opt() {
+                    Some(next) => next.and_hms_opt(0, 0, 0).unwrap().min(frame.end),
+                    None => frame.end,
+                };
+                if !matches!(cursor.weekday(), Weekday::Sat | Weekday::Sun) {
+                    open += next_day - cursor;
+                }
+                cursor = next_day;
+            }
+        }
+        open
+    }
+
+    /// Open time within `[start, end)` per weekday and hour of day, e.g. for a heatmap of
+    /// when the schedule is usually open. Indexed by `[weekday][hour]` with Monday as 0.
+    pub fn heatmap(&self, start: NaiveDateTime, end: NaiveDateTime) -> [[Duration; 24]; 7] {
+        let mut buckets = [[Duration::zero(); 24]; 7];
+        for frame in self.compute_open_frames(start, end) {
+            // Split the frame at each full hour so each part falls in a single bucket
+            let mut cursor = frame.start;
+            while cursor < frame.end {
+                let hour_start = cursor.date().and_hms_opt(cursor.hour(), 0, 0).unwrap();
+                let next_hour = (hour_start + Duration::hours(1)).min(frame.end);
+                buckets[cursor.weekday().num_days_from_monday() as usize]
+                    [cursor.hour() as usize] += next_hour - cursor;
+                cursor = next_hour;
+            }
+        }
+        buckets
+    }
+
+    /// One-line human readable summary of the given day, e.g.
+    /// `"2024-01-03: Open 09:00–12:00, Closed 12:00–13:00, Open 13:00–17:00."`.
+    ///
+    /// Adjacent frames with the same status are merged, and closed time before the first
+    /// and after the last opening is left out. Days without any opening are summarized
+    /// as `"Closed all day"`.
+    pub fn describe_day(&self, date: NaiveDate) -> String {
+        let day_start = date.and_hms_opt(0, 0, 0).unwrap();
+        let day_end = day_start + Duration::days(1);
+
+        // Merge adjacent frames with equal status into (off, start, end) runs
+        let mut runs: Vec<(bool, NaiveDateTime, NaiveDateTime)> = Vec::new();
+        for frame in self.compute_frames(day_start, day_end) {
+            match runs.last_mut() {
+                Some(last) if last.0 == frame.off && last.2 == frame.start => last.2 = frame.end,
+                _ => runs.push((frame.off, frame.start, frame.end)),
+            }
+        }
+        while runs.first().is_some_and(|run| run.0) {
+            runs.remove(0);
+        }
+        while runs.last().is_some_and(|run| run.0) {
+            runs.pop();
+        }
+
+        if runs.is_empty() {
+            return format!("{}: Closed all day.", date);
+        }
+
+        let format_time = |time: NaiveDateTime| {
+            if time == day_end {
+                "24:00".to_string()
+            } else {
+                time.format("%H:%M").to_string()
+            }
+        };
+        let parts: Vec<String> = runs
+            .iter()
+            .map(|(off, start, end)| {
+                let status = if *off { "Closed" } else { "Open" };
+                format!("{} {}–{}", status, format_time(*start), format_time(*end))
+            })
+            .collect();
+        format!("{}: {}.", date, parts.join(", "))
+    }
+
+    /// Longest continuous opening within `[start, end)`, merging adjacent open frames
+    /// regardless of payload. Ties are broken by the earliest start.
+    ///
+    /// Returns `None` if nothing is open in the range.
+    pub fn longest_open_span(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let frames = self.compute_frames(start, end);
+        let mut longest: Option<(NaiveDateTime, NaiveDateTime)> = None;
+        for (session_start, session_end) in sessions(&frames, false) {
+            match longest {
+                Some((longest_start, longest_end))
+                    if session_end - session_start <= longest_end - longest_start => {}
+                _ => longest = Some((session_start, session_end)),
+            }
+        }
+        longest
+    }
+
+    /// Start of the `window` long stretch within `[start, end)` with the most open time,
+    /// and that open time, e.g. for finding the busiest four hours. Ties are broken by the
+    /// earliest start.
+    ///
+    /// If the range is shorter than `window`, the whole range is evaluated.
+    pub fn max_open_in_window(
+        &self,
+        window: Duration,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> (NaiveDateTime, Duration) {
+        let open = sessions(&self.compute_frames(start, end), false);
+        let open_within = |from: NaiveDateTime| {
+            let to = (from + window).min(end);
+            open.iter()
+                .map(|&(open_start, open_end)| {
+                    (open_end.min(to) - open_start.max(from)).max(Duration::zero())
+                })
+                .sum::<Duration>()
+        };
+
+        // The open time only changes slope where a window edge meets a session edge, so a
+        // best window starts at a session start or ends at a session end
+        let latest_start = (end - window).max(start);
+        let mut candidates: Vec<NaiveDateTime> = open
+            .iter()
+            .flat_map(|&(open_start, open_end)| [open_start, open_end - window])
+            .map(|candidate| candidate.clamp(start, latest_start))
+            .collect();
+        candidates.extend([start, latest_start]);
+        candidates.sort();
+
+        let mut best = (start, Duration::zero());
+        for candidate in candidates {
+            let open_time = open_within(candidate);
+            if open_time > best.1 {
+                best = (candidate, open_time);
+            }
+        }
+        best
+    }
+
+    /// Status of each of the given dates, e.g. for rendering a month grid.
+    pub fn status_map(
+        &self,
+        dates: impl Iterator<Item = NaiveDate>,
+    ) -> Vec<(NaiveDate, DayStatus)> {
+        dates
+            .map(|date| {
+                let day_start = date.and_hms_opt(0, 0, 0).unwrap();
+                let frames = self.compute_frames(day_start, day_start + Duration::days(1));
+                let status = if frames.iter().all(|frame| frame.is_on()) {
+                    DayStatus::FullyOpen
+                } else if frames.iter().all(|frame| frame.is_off()) {
+                    DayStatus::FullyClosed
+                } else {
+                    DayStatus::Partial
+                };
+                (date, status)
+            })
+            .collect()
+    }
+
+    /// Number of times the status flips between open and closed within `[start, end)`.
+    /// Adjacent frames with the same status, e.g. differing payloads, do not count.
+    pub fn transition_count(&self, start: NaiveDateTime, end: NaiveDateTime) -> usize {
+        self.compute_frames(start, end)
+            .windows(2)
+            .filter(|pair| pair[0].off != pair[1].off)
+            .count()
+    }
+
+    /// Open status over `[start, end)` as `(boundary, open)` pairs, where each pair lasts
+    /// until the next boundary and the last one until `end`. The first pair is always at
+    /// `start`, and adjacent frames with the same status are collapsed into one pair.
+    pub fn collapse_to_states(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<(NaiveDateTime, bool)> {
+        let mut states: Vec<(NaiveDateTime, bool)> = Vec::new();
+        for frame in self.compute_frames(start, end) {
+            if states.last().map(|&(_, open)| open) != Some(frame.is_on()) {
+                states.push((frame.start.max(start), frame.is_on()));
+            }
+        }
+        states
+    }
+
+    /// Deterministic hash of the rules, e.g. for keying a frame cache.
+    ///
+    /// Generated frames are not included. Schedules with identical rules at identical
+    /// priorities hash equal, also across runs.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        self.rules.len().hash(&mut hasher);
+        for rules in self.rules.iter() {
+            rules.len().hash(&mut hasher);
+            for rule in rules.iter() {
+                rule.start.hash(&mut hasher);
+                rule.end.hash(&mut hasher);
+                rule.weekdays.hash(&mut hasher);
+                rule.off.hash(&mut hasher);
+                #[cfg(feature = "tz")]
+                rule.tz.map(|tz| tz.name()).hash(&mut hasher);
+                match &rule.payload {
+                    Some(payload) => serde_json::to_string(payload)
+                        .unwrap_or_else(|_| "<invalid payload>".to_string())
+                        .hash(&mut hasher),
+                    None => "None".hash(&mut hasher),
+                }
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Sets whether `to_frames_in_range` appends a zero-length end marker frame at the
+    /// (exclusive) end of the range, carrying the status at that instant. Since the end
+    /// is otherwise never included, this lets consumers show e.g. a closing time.
+    ///
+    /// The marker has zero duration, see `Frame::is_end_marker` to tell it apart.
+    pub fn set_include_end_marker(&mut self, include_end_marker: bool) {
+        self.include_end_marker = include_end_marker;
+    }
+
+    /// Sets a hook that is invoked for each frame when frames are finalized in
+    /// `to_frames_in_range`, e.g. for logging or enrichment. Replaces any previous hook.
+    pub fn set_frame_hook(&mut self, hook: FrameHook<T>) {
+        self.frame_hook = Some(Arc::from(hook));
+    }
+}
+
+/// Converts a local datetime in the given timezone to UTC. Ambiguous datetimes resolve to
+/// the earliest instant, and datetimes skipped by a transition are moved past the gap.
+#[cfg(feature = "tz")]
+fn local_to_utc(tz: chrono_tz::Tz, local: NaiveDateTime) -> NaiveDateTime {
+    use chrono::{LocalResult, TimeZone};
+
+    let mut candidate = local;
+    loop {
+        match tz.from_local_datetime(&candidate) {
+            LocalResult::Single(datetime) | LocalResult::Ambiguous(datetime, _) => {
+                return datetime.naive_utc()
+            }
+            LocalResult::None => candidate += Duration::minutes(15),
+        }
+    }
+}
+
+/// Parts of `[start, end)` not covered by any of the frames, as `(start, end)` pairs.
+/// The frames must not overlap.
+fn uncovered_parts<T>(
+    covered: &BTreeMap<NaiveDateTime, Frame<T>>,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Vec<(NaiveDateTime, NaiveDateTime)>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    let mut parts = Vec::new();
+    let mut cursor = start;
+    // A frame starting before the range may still reach into it
+    if let Some((_, frame)) = covered.range(..start).next_back() {
+        cursor = cursor.max(frame.end);
+    }
+    for frame in covered.range(start..end).map(|(_, frame)| frame) {
+        if cursor < frame.start {
+            parts.push((cursor, frame.start));
+        }
+        cursor = cursor.max(frame.end);
+    }
+    if cursor < end {
+        parts.push((cursor, end));
+    }
+    parts
+}
+
+/// Asserts in debug builds that frames are sorted, non-overlapping and contiguous, and
+/// cover exactly `[start, end)`. Does nothing in release builds.
+pub(crate) fn debug_assert_frames_invariant<T>(
+    frames: &[Frame<T>],
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    if start >= end {
+        debug_assert!(frames.is_empty(), "Frames generated for an empty range");
+        return;
+    }
+    debug_assert!(
+        frames.first().is_some_and(|frame| frame.start == start),
+        "Frames do not start at range start {}",
+        start
+    );
+    debug_assert!(
+        frames.last().is_some_and(|frame| frame.end == end),
+        "Frames do not end at range end {}",
+        end
+    );
+    for frame in frames.iter() {
+        debug_assert!(
+            frame.start < frame.end,
+            "Empty or inverted frame: {}",
+            frame
+        );
+    }
+    for pair in frames.windows(2) {
+        debug_assert!(
+            pair[0].end == pair[1].start,
+            "Frames are not contiguous: {} followed by {}",
+            pair[0],
+            pair[1]
+        );
+    }
+}
+
+/// Formats a time on a 12-hour clock without am/pm, e.g. `9` or `5:30`.
+fn short_time(time: NaiveTime) -> String {
+    let hour = match time.hour() % 12 {
+        0 => 12,
+        hour => hour,
+    };
+    match time.minute() {
+        0 => hour.to_string(),
+        minute => format!("{}:{:02}", hour, minute),
+    }
+}
+
+/// Resolves each schedule into frames within `[start, end)` and calls `pick` with the
+/// frame of every schedule for each stretch of time where none of them change. When
+/// `pick` returns a payload an open frame is produced for the stretch.
+///
+/// Consecutive stretches with matching payloads are merged.
+fn combine_open<T, F>(
+    schedules: &[&Availability<T>],
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    pick: F,
+) -> Vec<Frame<T>>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+    F: Fn(&[&Frame<T>]) -> Option<Option<T>>,
+{
+    let resolved: Vec<Vec<Frame<T>>> = schedules
+        .iter()
+        .map(|schedule| schedule.compute_frames(start, end))
+        .collect();
+    if resolved.is_empty() || resolved.iter().any(|frames| frames.is_empty()) {
+        return Vec::new();
+    }
+
+    let mut boundaries: Vec<NaiveDateTime> = resolved
+        .iter()
+        .flatten()
+        .map(|frame| frame.start)
+        .chain([end])
+        .collect();
+    boundaries.sort();
+    boundaries.dedup();
+
+    let mut frames: Vec<Frame<T>> = Vec::new();
+    let mut cursors = vec![0; resolved.len()];
+    for stretch in boundaries.windows(2) {
+        let current: Vec<&Frame<T>> = resolved
+            .iter()
+            .zip(cursors.iter_mut())
+            .map(|(frames, cursor)| {
+                while *cursor + 1 < frames.len() && frames[*cursor].end <= stretch[0] {
+                    *cursor += 1;
+                }
+                &frames[*cursor]
+            })
+            .collect();
+        let Some(payload) = pick(&current) else {
+            continue;
+        };
+        match frames.last_mut() {
+            Some(last)
+                if last.end == stretch[0]
+                    && serde_json::to_value(&last.payload).ok()
+                        == serde_json::to_value(&payload).ok() =>
+            {
+                last.end = stretch[1]
+            }
+            _ => frames.push(Frame::new(stretch[0], stretch[1], false, payload)),
+        }
+    }
+    frames
+}
+
+/// Merges adjacent frames with the given status into continuous `(start, end)` sessions,
+/// regardless of payload.
+fn sessions<T>(frames: &[Frame<T>], off: bool) -> Vec<(NaiveDateTime, NaiveDateTime)>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    let mut sessions: Vec<(NaiveDateTime, NaiveDateTime)> = Vec::new();
+    for frame in frames.iter().filter(|frame| frame.off == off) {
+        match sessions.last_mut() {
+            Some(last) if last.1 == frame.start => last.1 = frame.end,
+            _ => sessions.push((frame.start, frame.end)),
+        }
+    }
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        rule::relative_to_absolute_rules_in_range, rulebuilder::RuleBuilder, weekdays::Weekdays,
+    };
+
+    use super::*;
+    use chrono::{NaiveDate, NaiveDateTime, Timelike};
+    use serde_json::{json, Value};
+
+    fn create_datetime(
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        min: u32,
+        sec: u32,
+    ) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, min, sec)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_new_empty() {
+        let availability: Availability<Value> = Availability::new();
+        assert_eq!(availability.rules.len(), 1); // Should have base rule
+        assert_eq!(availability.frames.len(), 0); // No frames yet
+
+        // Check base rule properties
+        let base_rule = &availability.rules[0][0];
+        assert!(base_rule.off);
+        assert!(base_rule.is_absolute());
+        assert!(base_rule.payload.is_none());
+    }
+
+    #[test]
     fn test_add_rule_priority_validation() {
         let mut availability: Availability<Value> = Availability::new();
 
@@ -446,7 +2586,7 @@ mod tests {
         let result = availability.add_rule(rule, 0);
         assert!(result.is_err());
         assert_eq!(
-            result.unwrap_err(),
+            result.unwrap_err().to_string(),
             "Priority 0 is reserved for base rule and cannot be modified"
         );
     }
@@ -476,9 +2616,18 @@ mod tests {
         )
         .unwrap();
 
-        let result = availability.add_rule(rule2, 1);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("overlaps with existing rule"));
+        let error = availability.add_rule(rule2, 1).unwrap_err();
+        assert!(error.to_string().contains("overlaps with existing rule"));
+        assert_eq!(
+            error,
+            AvailabilityError::AbsoluteOverlap {
+                priority: 1,
+                new_start: create_datetime(2024, 1, 1, 12, 0, 0),
+                new_end: create_datetime(2024, 1, 1, 18, 0, 0),
+                existing_start: create_datetime(2024, 1, 1, 9, 0, 0),
+                existing_end: create_datetime(2024, 1, 1, 17, 0, 0),
+            }
+        );
     }
 
     #[test]
@@ -489,7 +2638,7 @@ mod tests {
         let rule1 = Rule::new(
             create_datetime(2024, 1, 1, 9, 0, 0),
             create_datetime(2024, 1, 31, 17, 0, 0),
-            Some(MONDAY | TUESDAY | WEDNESDAY),
+            Some(Weekdays::MONDAY | Weekdays::TUESDAY | Weekdays::WEDNESDAY),
             false,
             None,
         )
@@ -500,7 +2649,7 @@ mod tests {
         let rule2 = Rule::new(
             create_datetime(2024, 1, 1, 9, 0, 0),
             create_datetime(2024, 1, 31, 17, 0, 0),
-            Some(WEDNESDAY | THURSDAY | FRIDAY),
+            Some(Weekdays::WEDNESDAY | Weekdays::THURSDAY | Weekdays::FRIDAY),
             false,
             None,
         )
@@ -508,7 +2657,37 @@ mod tests {
 
         let result = availability.add_rule(rule2, 1);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("clashing weekdays"));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("clashing weekdays"));
+    }
+
+    #[test]
+    fn test_add_rule_identical_relative_is_noop() {
+        let mut availability: Availability<Value> = Availability::new();
+
+        let rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240131170000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+            .payload(json!({"type": "regular"}))
+            .build()
+            .unwrap();
+
+        availability.add_rule(rule.clone(), 1).unwrap();
+        availability.add_rule(rule.clone(), 1).unwrap();
+        assert_eq!(availability.rules[1].len(), 1);
+
+        // Same window and weekdays but different payload still clashes
+        let mut other = rule;
+        other.payload = Some(json!({"type": "special"}));
+        let result = availability.add_rule(other, 1);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("clashing weekdays"));
     }
 
     #[test]
@@ -581,16 +2760,17 @@ mod tests {
 
         // Try to remove from non-existent priority
         let result = availability.remove_rule_by_index(1, 0);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Priority 1 does not exist"));
+        assert!(matches!(
+            result,
+            Err(AvailabilityError::RuleNotFound {
+                priority: 1,
+                index: 0
+            })
+        ));
 
         // Try to remove from priority 0
         let result = availability.remove_rule_by_index(0, 0);
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err(),
-            "Priority 0 is reserved for base rule and cannot be modified"
-        );
+        assert!(matches!(result, Err(AvailabilityError::ReservedPriority)));
     }
 
     #[test]
@@ -787,4 +2967,3172 @@ mod tests {
             assert!(availability.frames[1].off);
         }
     }
+
+    #[test]
+    fn test_open_ratio_for_weekday() {
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("241231170000")
+            .wednesday()
+            .build()
+            .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+
+        // Four full weeks starting on Monday 2024-01-01
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 29, 0, 0, 0);
+        let ratio = availability.open_ratio_for_weekday(Weekday::Wed, start, end);
+        assert!((ratio - 8.0 / 24.0).abs() < f64::EPSILON);
+
+        // Closed every Thursday
+        let ratio = availability.open_ratio_for_weekday(Weekday::Thu, start, end);
+        assert_eq!(ratio, 0.0);
+    }
+
+    #[test]
+    fn test_clone_is_independent() {
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 8, 0, 0),
+            create_datetime(2024, 1, 1, 13, 0, 0),
+        );
+
+        let mut snapshot = availability.clone();
+        assert_eq!(snapshot.rules.len(), availability.rules.len());
+        assert_eq!(snapshot.frames.len(), availability.frames.len());
+
+        let other = Rule::new(
+            create_datetime(2024, 1, 2, 9, 0, 0),
+            create_datetime(2024, 1, 2, 12, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        snapshot.add_rule(other, 2).unwrap();
+        snapshot.clear_frames();
+
+        assert_eq!(availability.rules.len(), 2);
+        assert_eq!(availability.rules[1].len(), 1);
+        assert_eq!(availability.frames.len(), 3);
+    }
+
+    #[test]
+    fn test_open_until_and_close_until() {
+        let mut availability: Availability<Value> = Availability::new();
+        let from = create_datetime(2024, 1, 1, 9, 0, 0);
+        availability.open_until(from, 1).unwrap();
+
+        assert!(!availability.is_open(create_datetime(2024, 1, 1, 8, 59, 59)));
+        assert!(availability.is_open(from));
+        assert!(availability.is_open(create_datetime(2024, 6, 15, 3, 0, 0)));
+        assert!(availability.is_open(create_datetime(2999, 12, 31, 23, 59, 59)));
+
+        let closed_from = create_datetime(2025, 1, 1, 0, 0, 0);
+        availability.close_until(closed_from, 2).unwrap();
+        assert!(availability.is_open(create_datetime(2024, 12, 31, 23, 59, 59)));
+        assert!(!availability.is_open(closed_from));
+        assert!(!availability.is_open(create_datetime(2500, 1, 1, 0, 0, 0)));
+    }
+
+    #[test]
+    fn test_to_frames_in_range_capped() {
+        let mut availability: Availability<Value> = Availability::new();
+        // Ten one-hour open rules with one-hour gaps => 20 frames over the day
+        for i in 0..10 {
+            let rule = Rule::new(
+                create_datetime(2024, 1, 1, 2 * i, 0, 0),
+                create_datetime(2024, 1, 1, 2 * i + 1, 0, 0),
+                None,
+                false,
+                None,
+            )
+            .unwrap();
+            availability.add_rule(rule, 1).unwrap();
+        }
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 1, 20, 0, 0);
+
+        let result = availability.to_frames_in_range_capped(start, end, 5);
+        assert_eq!(
+            result,
+            Err(AvailabilityError::FrameLimitExceeded { max: 5, actual: 6 })
+        );
+        assert!(availability.frames.is_empty());
+
+        availability
+            .to_frames_in_range_capped(start, end, 20)
+            .unwrap();
+        assert_eq!(availability.frames.len(), 20);
+    }
+
+    #[test]
+    fn test_to_frames_in_range_capped_stops_early() {
+        let mut availability: Availability<Value> = Availability::new();
+        // Tens of thousands of frames over the whole base range
+        let rule = RuleBuilder::new()
+            .start_time_str("000101090000")
+            .end_time_str("991231170000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+            .build()
+            .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+        let base_rule = Rule::<Value>::base_rule();
+
+        // Stops at the first frame over the limit instead of resolving all of them
+        let result = availability.to_frames_in_range_capped(base_rule.start, base_rule.end, 10);
+        assert_eq!(
+            result,
+            Err(AvailabilityError::FrameLimitExceeded {
+                max: 10,
+                actual: 11
+            })
+        );
+        assert!(availability.frames.is_empty());
+    }
+
+    #[test]
+    fn test_describe_day() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open_rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240131170000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+            .build()
+            .unwrap();
+        let lunch_rule = RuleBuilder::new()
+            .start_time_str("240103120000")
+            .end_time_str("240103130000")
+            .off(true)
+            .build()
+            .unwrap();
+        availability.add_rule(open_rule, 1).unwrap();
+        availability.add_rule(lunch_rule, 2).unwrap();
+
+        assert_eq!(
+            availability.describe_day(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap()),
+            "2024-01-03: Open 09:00–12:00, Closed 12:00–13:00, Open 13:00–17:00."
+        );
+        assert_eq!(
+            availability.describe_day(NaiveDate::from_ymd_opt(2024, 1, 4).unwrap()),
+            "2024-01-04: Open 09:00–17:00."
+        );
+        // Saturday
+        assert_eq!(
+            availability.describe_day(NaiveDate::from_ymd_opt(2024, 1, 6).unwrap()),
+            "2024-01-06: Closed all day."
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let build = || {
+            let mut availability: Availability<Value> = Availability::new();
+            let rule = RuleBuilder::new()
+                .start_time_str("240101090000")
+                .end_time_str("240131170000")
+                .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+                .payload(json!({"staff": 3}))
+                .build()
+                .unwrap();
+            availability.add_rule(rule, 1).unwrap();
+            availability
+        };
+
+        let first = build();
+        let mut second = build();
+        // Frames are not part of the hash
+        second.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 8, 0, 0, 0),
+        );
+        assert_eq!(first.content_hash(), second.content_hash());
+
+        second.rules[1][0].payload = Some(json!({"staff": 4}));
+        assert_ne!(first.content_hash(), second.content_hash());
+    }
+
+    #[test]
+    fn test_is_open_skips_rules_by_bounds() {
+        let mut availability: Availability<Value> = Availability::new();
+        // Thousands of week-long relative rules, Mondays 09:00 to 17:00
+        let first_monday = create_datetime(2001, 1, 1, 9, 0, 0);
+        for week in 0..2000 {
+            let start = first_monday + Duration::weeks(week);
+            let rule = Rule::new(
+                start,
+                start + Duration::days(6) + Duration::hours(8),
+                Some(Weekdays::MONDAY),
+                false,
+                None,
+            )
+            .unwrap();
+            availability.add_rule(rule, 1).unwrap();
+        }
+
+        // Higher priority Monday closures whose bounds exclude the queried Monday: one
+        // only in 2002, the other only in the evening. Judged by weekday alone, either
+        // would close the schedule
+        let closed_in_2002 = Rule::new(
+            create_datetime(2002, 1, 7, 9, 0, 0),
+            create_datetime(2002, 12, 30, 11, 0, 0),
+            Some(Weekdays::MONDAY),
+            true,
+            None,
+        )
+        .unwrap();
+        let closed_evenings = Rule::new(
+            create_datetime(2001, 1, 1, 18, 0, 0),
+            create_datetime(2041, 1, 1, 19, 0, 0),
+            Some(Weekdays::MONDAY),
+            true,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(closed_in_2002, 2).unwrap();
+        availability.add_rule(closed_evenings, 3).unwrap();
+
+        let monday = first_monday + Duration::weeks(1000) + Duration::hours(1);
+        assert!(availability.is_open(monday));
+        // Decided by the one week-long rule containing the Monday, not by any other
+        assert_eq!(availability.rule_at(monday), Some((1, 1000)));
+        assert!(!availability.is_open(monday + Duration::days(1)));
+        assert!(!availability.is_open(monday + Duration::hours(10)));
+        assert_eq!(
+            availability.rule_at(monday + Duration::hours(8)),
+            Some((3, 0))
+        );
+    }
+
+    #[cfg(feature = "columnar")]
+    #[test]
+    fn test_to_columns() {
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            None,
+            false,
+            Some(json!({"info": "morning shift"})),
+        )
+        .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 8, 0, 0),
+            create_datetime(2024, 1, 1, 13, 0, 0),
+        );
+
+        let (starts, ends, offs, payloads) = availability.to_columns();
+        let count = availability.frames().len();
+        assert_eq!(starts.len(), count);
+        assert_eq!(ends.len(), count);
+        assert_eq!(offs.len(), count);
+        assert_eq!(payloads.len(), count);
+        assert_eq!(offs, vec![true, false, true]);
+        assert_eq!(payloads[1], Some(json!({"info": "morning shift"})));
+    }
+
+    #[test]
+    fn test_longest_open_span() {
+        let mut availability: Availability<Value> = Availability::new();
+        let morning = Rule::new(
+            create_datetime(2024, 1, 1, 8, 0, 0),
+            create_datetime(2024, 1, 1, 10, 0, 0),
+            None,
+            false,
+            Some(json!({"shift": "early"})),
+        )
+        .unwrap();
+        // Adjacent open frames with different payloads form one session
+        let late_morning = Rule::new(
+            create_datetime(2024, 1, 1, 10, 0, 0),
+            create_datetime(2024, 1, 1, 11, 0, 0),
+            None,
+            false,
+            Some(json!({"shift": "late"})),
+        )
+        .unwrap();
+        let afternoon = Rule::new(
+            create_datetime(2024, 1, 1, 13, 0, 0),
+            create_datetime(2024, 1, 1, 15, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(morning, 1).unwrap();
+        availability.add_rule(late_morning, 1).unwrap();
+        availability.add_rule(afternoon, 1).unwrap();
+
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+        assert_eq!(
+            availability.longest_open_span(start, end),
+            Some((
+                create_datetime(2024, 1, 1, 8, 0, 0),
+                create_datetime(2024, 1, 1, 11, 0, 0)
+            ))
+        );
+
+        // Nothing open
+        assert_eq!(
+            availability.longest_open_span(
+                create_datetime(2024, 1, 2, 0, 0, 0),
+                create_datetime(2024, 1, 3, 0, 0, 0)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_to_frames_in_range_with_eq() {
+        let mut availability: Availability<Value> = Availability::new();
+        for (from, to, shift) in [(8, 10, "early"), (10, 12, "late")] {
+            let rule = Rule::new(
+                create_datetime(2024, 1, 1, from, 0, 0),
+                create_datetime(2024, 1, 1, to, 0, 0),
+                None,
+                false,
+                Some(json!({ "shift": shift })),
+            )
+            .unwrap();
+            availability.add_rule(rule, 1).unwrap();
+        }
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+
+        availability.to_frames_in_range(start, end);
+        assert_eq!(availability.frames.len(), 4);
+
+        // Treat all open frames as equivalent
+        availability.to_frames_in_range_with_eq(start, end, |a, b| a.is_on() && b.is_on());
+        assert_eq!(availability.frames.len(), 3);
+        let open = &availability.frames[1];
+        assert_eq!(open.start, create_datetime(2024, 1, 1, 8, 0, 0));
+        assert_eq!(open.end, create_datetime(2024, 1, 1, 12, 0, 0));
+        assert_eq!(open.payload.as_ref().unwrap()["shift"], "early");
+    }
+
+    #[test]
+    fn test_validate_base_rule_invariant() {
+        let mut availability: Availability<Value> = Availability::new();
+        assert!(availability.validate_base_rule_invariant().is_ok());
+
+        // Tampered range
+        availability.rules[0][0].end = create_datetime(2024, 1, 1, 0, 0, 0);
+        assert!(matches!(
+            availability.validate_base_rule_invariant(),
+            Err(AvailabilityError::BaseRuleInvariant(_))
+        ));
+
+        // Extra rule at priority 0
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        availability.rules[0].push(rule);
+        assert!(availability.validate_base_rule_invariant().is_err());
+
+        // Missing priority 0
+        availability.rules.clear();
+        assert!(availability.validate_base_rule_invariant().is_err());
+    }
+
+    #[test]
+    fn test_status_map() {
+        let mut availability: Availability<Value> = Availability::new();
+        let weekdays = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240131170000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+            .build()
+            .unwrap();
+        let saturday = RuleBuilder::new()
+            .start_time_str("240106000000")
+            .end_time_str("240107000000")
+            .build()
+            .unwrap();
+        availability.add_rule(weekdays, 1).unwrap();
+        availability.add_rule(saturday, 2).unwrap();
+
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let statuses = availability.status_map(monday.iter_days().take(7));
+        let expected = [
+            DayStatus::Partial,
+            DayStatus::Partial,
+            DayStatus::Partial,
+            DayStatus::Partial,
+            DayStatus::Partial,
+            DayStatus::FullyOpen,
+            DayStatus::FullyClosed,
+        ];
+        assert_eq!(statuses.len(), 7);
+        for (i, (date, status)) in statuses.iter().enumerate() {
+            assert_eq!(*date, monday + Duration::days(i as i64));
+            assert_eq!(*status, expected[i]);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            None,
+            false,
+            Some(json!({"version": 1})),
+        )
+        .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+        let snapshot = availability.snapshot();
+        let hash = availability.content_hash();
+
+        availability.remove_rule_by_index(1, 0).unwrap();
+        assert_ne!(availability.content_hash(), hash);
+
+        let mut restored = Availability::from_snapshot(snapshot);
+        assert_eq!(restored.content_hash(), hash);
+        assert!(restored.frames().is_empty());
+        restored.to_frames_in_range(
+            create_datetime(2024, 1, 1, 8, 0, 0),
+            create_datetime(2024, 1, 1, 13, 0, 0),
+        );
+        assert_eq!(restored.frames().len(), 3);
+        assert_eq!(restored.frames()[1].payload, Some(json!({"version": 1})));
+    }
+
+    #[test]
+    fn test_to_frames_in_range_multiple_overrides() {
+        // A low priority frame overridden by several higher priority frames, and a high
+        // priority frame spanning several lower priority frames
+        let mut availability: Availability<Value> = Availability::new();
+        let day = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(day, 1).unwrap();
+        for (from, to) in [(10, 11), (12, 13)] {
+            let closed = Rule::new(
+                create_datetime(2024, 1, 1, from, 0, 0),
+                create_datetime(2024, 1, 1, to, 0, 0),
+                None,
+                true,
+                None,
+            )
+            .unwrap();
+            availability.add_rule(closed, 2).unwrap();
+        }
+        let evening = Rule::new(
+            create_datetime(2024, 1, 1, 16, 0, 0),
+            create_datetime(2024, 1, 1, 20, 0, 0),
+            None,
+            false,
+            Some(json!({"info": "evening"})),
+        )
+        .unwrap();
+        availability.add_rule(evening, 3).unwrap();
+
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 8, 0, 0),
+            create_datetime(2024, 1, 1, 21, 0, 0),
+        );
+        let bounds: Vec<(u32, u32, bool)> = availability
+            .frames
+            .iter()
+            .map(|frame| (frame.start.hour(), frame.end.hour(), frame.off))
+            .collect();
+        assert_eq!(
+            bounds,
+            vec![
+                (8, 9, true),
+                (9, 10, false),
+                (10, 11, true),
+                (11, 12, false),
+                (12, 13, true),
+                (13, 16, false),
+                (16, 20, false),
+                (20, 21, true),
+            ]
+        );
+        assert_eq!(
+            availability.frames[6].payload,
+            Some(json!({"info": "evening"}))
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "Frames are not contiguous")]
+    fn test_frames_invariant_detects_gap() {
+        let start = create_datetime(2024, 1, 1, 8, 0, 0);
+        let end = create_datetime(2024, 1, 1, 12, 0, 0);
+        let frames: Vec<Frame<Value>> = vec![
+            Frame::new(start, create_datetime(2024, 1, 1, 9, 0, 0), true, None),
+            Frame::new(create_datetime(2024, 1, 1, 10, 0, 0), end, false, None),
+        ];
+        debug_assert_frames_invariant(&frames, start, end);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "Frames do not end at range end")]
+    fn test_frames_invariant_detects_short_coverage() {
+        let start = create_datetime(2024, 1, 1, 8, 0, 0);
+        let end = create_datetime(2024, 1, 1, 12, 0, 0);
+        let frames: Vec<Frame<Value>> = vec![Frame::new(
+            start,
+            create_datetime(2024, 1, 1, 11, 0, 0),
+            true,
+            None,
+        )];
+        debug_assert_frames_invariant(&frames, start, end);
+    }
+
+    #[cfg(feature = "tz")]
+    #[test]
+    fn test_to_utc_frames_in_range() {
+        let mut availability: Availability<Value> = Availability::new();
+        let utc_rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101100000")
+            .tz(chrono_tz::UTC)
+            .payload(json!({"site": "London"}))
+            .build()
+            .unwrap();
+        // 09:00 to 10:00 in New York is 14:00 to 15:00 UTC in winter
+        let new_york_rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101100000")
+            .tz(chrono_tz::America::New_York)
+            .payload(json!({"site": "New York"}))
+            .build()
+            .unwrap();
+        availability.add_rule(utc_rule, 1).unwrap();
+        availability.add_rule(new_york_rule, 2).unwrap();
+
+        let frames = availability.to_utc_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+        let open: Vec<&Frame<Value>> = frames.iter().filter(|frame| frame.is_on()).collect();
+        assert_eq!(open.len(), 2);
+        assert_eq!(open[0].start, create_datetime(2024, 1, 1, 9, 0, 0));
+        assert_eq!(open[0].end, create_datetime(2024, 1, 1, 10, 0, 0));
+        assert_eq!(open[0].payload.as_ref().unwrap()["site"], "London");
+        assert_eq!(open[1].start, create_datetime(2024, 1, 1, 14, 0, 0));
+        assert_eq!(open[1].end, create_datetime(2024, 1, 1, 15, 0, 0));
+        assert_eq!(open[1].payload.as_ref().unwrap()["site"], "New York");
+    }
+
+    #[test]
+    fn test_transition_count() {
+        let mut availability: Availability<Value> = Availability::new();
+        for (from, to) in [(9, 12), (13, 17)] {
+            let rule = Rule::new(
+                create_datetime(2024, 1, 1, from, 0, 0),
+                create_datetime(2024, 1, 1, to, 0, 0),
+                None,
+                false,
+                None,
+            )
+            .unwrap();
+            availability.add_rule(rule, 1).unwrap();
+        }
+
+        // closed -> open -> closed -> open -> closed
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+        assert_eq!(availability.transition_count(start, end), 4);
+
+        // Range starting while open
+        let start = create_datetime(2024, 1, 1, 10, 0, 0);
+        assert_eq!(availability.transition_count(start, end), 3);
+    }
+
+    #[test]
+    fn test_merge_adjacent_off_gaps() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        let closed = Rule::new(
+            create_datetime(2024, 1, 1, 14, 0, 0),
+            create_datetime(2024, 1, 1, 16, 0, 0),
+            None,
+            true,
+            Some(json!({"reason": "maintenance"})),
+        )
+        .unwrap();
+        availability.add_rule(open, 1).unwrap();
+        availability.add_rule(closed, 1).unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 8, 0, 0),
+            create_datetime(2024, 1, 1, 18, 0, 0),
+        );
+        // base | open | base | maintenance | base
+        assert_eq!(availability.frames.len(), 5);
+
+        availability.merge_adjacent_off_gaps();
+        assert_eq!(availability.frames.len(), 3);
+        let closed = &availability.frames[2];
+        assert!(closed.off);
+        assert_eq!(closed.start, create_datetime(2024, 1, 1, 12, 0, 0));
+        assert_eq!(closed.end, create_datetime(2024, 1, 1, 18, 0, 0));
+        assert_eq!(closed.payload, Some(json!({"reason": "maintenance"})));
+    }
+
+    #[test]
+    fn test_compute_open_frames() {
+        let mut availability: Availability<Value> = Availability::new();
+        let weekdays = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240131170000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+            .build()
+            .unwrap();
+        let closure = RuleBuilder::new()
+            .start_time_str("240103120000")
+            .end_time_str("240103130000")
+            .off(true)
+            .build()
+            .unwrap();
+        availability.add_rule(weekdays, 1).unwrap();
+        availability.add_rule(closure, 2).unwrap();
+
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 8, 0, 0, 0);
+        let open = availability.compute_open_frames(start, end);
+        assert!(open.iter().all(|frame| frame.is_on()));
+        // Five weekdays, with Wednesday split by the closure
+        assert_eq!(open.len(), 6);
+
+        availability.to_frames_in_range(start, end);
+        let expected: Vec<(NaiveDateTime, NaiveDateTime)> = availability
+            .frames()
+            .iter()
+            .filter(|frame| frame.is_on())
+            .map(|frame| (frame.start, frame.end))
+            .collect();
+        let actual: Vec<(NaiveDateTime, NaiveDateTime)> =
+            open.iter().map(|frame| (frame.start, frame.end)).collect();
+        assert_eq!(actual, expected);
+
+        // Off rules below every on rule are skipped without changing the result
+        let mut availability: Availability<Value> = Availability::new();
+        let low_closure = RuleBuilder::new()
+            .start_time_str("240101000000")
+            .end_time_str("240108000000")
+            .off(true)
+            .build()
+            .unwrap();
+        let morning = RuleBuilder::new()
+            .start_time_str("240102090000")
+            .end_time_str("240102120000")
+            .payload(json!({"shift": "morning"}))
+            .build()
+            .unwrap();
+        availability.add_rule(low_closure, 1).unwrap();
+        availability.add_rule(morning, 2).unwrap();
+        let open = availability.compute_open_frames(start, end);
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].start, create_datetime(2024, 1, 2, 9, 0, 0));
+        assert_eq!(open[0].payload, Some(json!({"shift": "morning"})));
+
+        // Open by default, the gaps are open frames
+        let availability: Availability<Value> = Availability::always_open_except(&[(
+            create_datetime(2024, 1, 3, 0, 0, 0),
+            create_datetime(2024, 1, 4, 0, 0, 0),
+        )]);
+        let open: Vec<(NaiveDateTime, NaiveDateTime)> = availability
+            .compute_open_frames(start, end)
+            .iter()
+            .map(|frame| (frame.start, frame.end))
+            .collect();
+        assert_eq!(
+            open,
+            vec![
+                (start, create_datetime(2024, 1, 3, 0, 0, 0)),
+                (create_datetime(2024, 1, 4, 0, 0, 0), end),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_open_range() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        let closure = Rule::new(
+            create_datetime(2024, 1, 1, 11, 0, 0),
+            create_datetime(2024, 1, 1, 11, 30, 0),
+            None,
+            true,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(open, 1).unwrap();
+        availability.add_rule(closure, 2).unwrap();
+
+        assert!(!availability.is_open_range(
+            create_datetime(2024, 1, 1, 10, 0, 0),
+            create_datetime(2024, 1, 1, 12, 0, 0)
+        ));
+        assert!(availability.is_open_range(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 11, 0, 0)
+        ));
+        assert!(availability.is_open_range(
+            create_datetime(2024, 1, 1, 11, 30, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0)
+        ));
+        // Runs past closing time
+        assert!(!availability.is_open_range(
+            create_datetime(2024, 1, 1, 16, 0, 0),
+            create_datetime(2024, 1, 1, 18, 0, 0)
+        ));
+    }
+
+    #[test]
+    fn test_forever_relative_rule_expands_only_window() {
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = RuleBuilder::new().forever().monday().build().unwrap();
+        availability.add_rule(rule.clone(), 1).unwrap();
+
+        // 2024-01-01 is a Monday
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 8, 0, 0, 0);
+
+        // Only that week's Monday is materialized
+        let materialized = relative_to_absolute_rules_in_range(rule, start, end).unwrap();
+        assert_eq!(materialized.len(), 1);
+        assert_eq!(materialized[0].start, start);
+        assert_eq!(materialized[0].end, create_datetime(2024, 1, 2, 0, 0, 0));
+
+        availability.to_frames_in_range(start, end);
+        let frames: Vec<(NaiveDateTime, NaiveDateTime, bool)> = availability
+            .frames
+            .iter()
+            .map(|frame| (frame.start, frame.end, frame.off))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                (start, create_datetime(2024, 1, 2, 0, 0, 0), false),
+                (create_datetime(2024, 1, 2, 0, 0, 0), end, true),
+            ]
+        );
+
+        // Evaluated directly, far from any expanded window
+        assert!(availability.is_open(create_datetime(2999, 12, 30, 23, 59, 59)));
+        assert!(!availability.is_open(create_datetime(2999, 12, 31, 12, 0, 0)));
+    }
+
+    #[test]
+    fn test_with_rules_capacity_from() {
+        let mut other: Availability<Value> = Availability::new();
+        for day in 1..=3 {
+            let rule = Rule::new(
+                create_datetime(2024, 1, day, 9, 0, 0),
+                create_datetime(2024, 1, day, 17, 0, 0),
+                None,
+                false,
+                None,
+            )
+            .unwrap();
+            other.add_rule(rule, 2).unwrap();
+        }
+
+        let mut availability = Availability::with_rules_capacity_from(&other);
+        assert_eq!(availability.rules.len(), other.rules.len());
+        assert_eq!(availability.rules[0].len(), 1);
+        assert!(availability.rules[2].is_empty());
+        assert!(availability.rules[2].capacity() >= other.rules[2].len());
+
+        let capacity = availability.rules[2].capacity();
+        for rule in other.rules[2].iter() {
+            availability.add_rule(rule.clone(), 2).unwrap();
+        }
+        // Added rules fit without reallocating
+        assert_eq!(availability.rules[2].capacity(), capacity);
+    }
+
+    #[test]
+    fn test_explain() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        let closure = Rule::new(
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            create_datetime(2024, 1, 1, 13, 0, 0),
+            None,
+            true,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(open, 1).unwrap();
+        availability.add_rule(closure, 3).unwrap();
+
+        assert_eq!(
+            availability.explain(create_datetime(2024, 1, 1, 12, 30, 0)),
+            "priority 3 off-rule matched (wins); priority 1 on-rule matched but lower priority; base closed."
+        );
+        assert_eq!(
+            availability.explain(create_datetime(2024, 1, 1, 10, 0, 0)),
+            "priority 3 no rule matched; priority 1 on-rule matched (wins); base closed."
+        );
+        assert_eq!(
+            availability.explain(create_datetime(2024, 1, 1, 18, 0, 0)),
+            "priority 3 no rule matched; priority 1 no rule matched; base closed (wins)."
+        );
+    }
+
+    #[test]
+    fn test_subtract() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(open, 1).unwrap();
+
+        let mut booked: Availability<Value> = Availability::new();
+        let busy = Rule::new(
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            create_datetime(2024, 1, 1, 13, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        booked.add_rule(busy, 1).unwrap();
+
+        let free = availability.subtract(
+            &booked,
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+        let actual: Vec<(NaiveDateTime, NaiveDateTime)> =
+            free.iter().map(|frame| (frame.start, frame.end)).collect();
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    create_datetime(2024, 1, 1, 9, 0, 0),
+                    create_datetime(2024, 1, 1, 12, 0, 0)
+                ),
+                (
+                    create_datetime(2024, 1, 1, 13, 0, 0),
+                    create_datetime(2024, 1, 1, 17, 0, 0)
+                ),
+            ]
+        );
+        assert!(free.iter().all(|frame| frame.is_on()));
+    }
+
+    #[test]
+    fn test_retain_rules() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        let maintenance = Rule::new(
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            create_datetime(2024, 1, 1, 13, 0, 0),
+            None,
+            true,
+            Some(json!("maintenance")),
+        )
+        .unwrap();
+        availability.add_rule(open, 1).unwrap();
+        availability.add_rule(maintenance, 3).unwrap();
+        assert_eq!(availability.rules.len(), 4);
+
+        availability.retain_rules(|_, rule| !rule.off);
+
+        // The base rule stays and the empty top levels are removed
+        assert_eq!(availability.rules.len(), 2);
+        assert!(availability.rules[0][0].off);
+        assert_eq!(availability.rules[1].len(), 1);
+        assert!(availability.is_open(create_datetime(2024, 1, 1, 12, 30, 0)));
+    }
+
+    #[test]
+    fn test_preview_add() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(open.clone(), 1).unwrap();
+
+        let closure = Rule::new(
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            create_datetime(2024, 1, 1, 13, 0, 0),
+            None,
+            true,
+            Some(json!("lunch")),
+        )
+        .unwrap();
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+
+        let preview = availability
+            .preview_add(closure.clone(), 2, start, end)
+            .unwrap();
+        // Nothing was added by the preview
+        assert_eq!(availability.rules.len(), 2);
+
+        // Overlapping rules at the same priority are rejected
+        assert!(matches!(
+            availability.preview_add(open, 1, start, end),
+            Err(AvailabilityError::AbsoluteOverlap { .. })
+        ));
+
+        availability.add_rule(closure, 2).unwrap();
+        availability.to_frames_in_range(start, end);
+        let as_tuples = |frames: &[Frame<Value>]| {
+            frames
+                .iter()
+                .map(|frame| (frame.start, frame.end, frame.off, frame.payload.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(as_tuples(&preview), as_tuples(&availability.frames));
+    }
+
+    #[test]
+    fn test_runs() {
+        let mut availability: Availability<Value> = Availability::new();
+        let morning = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            None,
+            false,
+            Some(json!("morning")),
+        )
+        .unwrap();
+        let afternoon = Rule::new(
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            Some(json!("afternoon")),
+        )
+        .unwrap();
+        availability.add_rule(morning, 1).unwrap();
+        availability.add_rule(afternoon, 1).unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+
+        let runs = availability.runs();
+        let shape: Vec<(bool, usize)> = runs
+            .iter()
+            .map(|(off, frames)| (*off, frames.len()))
+            .collect();
+        assert_eq!(shape, vec![(true, 1), (false, 2), (true, 1)]);
+        assert_eq!(runs[1].1[0].payload, Some(json!("morning")));
+        assert_eq!(runs[1].1[1].payload, Some(json!("afternoon")));
+    }
+
+    #[test]
+    fn test_open_duration_business_days() {
+        let mut availability: Availability<Value> = Availability::new();
+        // Open 09:00-17:00 every day of the week starting Monday 2024-01-01
+        let rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240107170000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri", "sat", "sun"])
+            .build()
+            .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+
+        let open = availability.open_duration_business_days(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 8, 0, 0, 0),
+        );
+        // The weekend is excluded
+        assert_eq!(open, Duration::hours(5 * 8));
+    }
+
+    #[test]
+    fn test_from_ical() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   VERSION:2.0\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART:20240101T090000\r\n\
+                   DTEND:20240101T120000\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART:20240101T130000\r\n\
+                   DTEND:20240101T170000\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+
+        let mut availability: Availability<Value> = Availability::from_ical(ics, 1).unwrap();
+        assert_eq!(availability.rules[1].len(), 2);
+        assert!(availability.rules[1].iter().all(|rule| !rule.off));
+
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+        let open: Vec<(NaiveDateTime, NaiveDateTime)> = availability
+            .frames
+            .iter()
+            .filter(|frame| frame.is_on())
+            .map(|frame| (frame.start, frame.end))
+            .collect();
+        assert_eq!(
+            open,
+            vec![
+                (
+                    create_datetime(2024, 1, 1, 9, 0, 0),
+                    create_datetime(2024, 1, 1, 12, 0, 0)
+                ),
+                (
+                    create_datetime(2024, 1, 1, 13, 0, 0),
+                    create_datetime(2024, 1, 1, 17, 0, 0)
+                ),
+            ]
+        );
+
+        // Priority 0 is still reserved for the base rule
+        assert!(Availability::<Value>::from_ical(ics, 0).is_err());
+    }
+
+    #[test]
+    fn test_shift_rule() {
+        let mut availability: Availability<Value> = Availability::new();
+        let morning = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        let afternoon = Rule::new(
+            create_datetime(2024, 1, 1, 13, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(morning, 1).unwrap();
+        availability.add_rule(afternoon, 1).unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+
+        // Shifting the morning an hour later still leaves it before the afternoon
+        availability.shift_rule(1, 0, Duration::hours(1)).unwrap();
+        assert_eq!(
+            availability.rules[1][0].start,
+            create_datetime(2024, 1, 1, 10, 0, 0)
+        );
+        assert_eq!(
+            availability.rules[1][0].end,
+            create_datetime(2024, 1, 1, 13, 0, 0)
+        );
+        assert!(availability.frames.is_empty());
+
+        // Another hour would overlap the afternoon
+        let result = availability.shift_rule(1, 0, Duration::hours(1));
+        assert!(matches!(
+            result,
+            Err(AvailabilityError::AbsoluteOverlap { .. })
+        ));
+        assert_eq!(
+            availability.rules[1][0].start,
+            create_datetime(2024, 1, 1, 10, 0, 0)
+        );
+        assert_eq!(availability.rules[1].len(), 2);
+
+        assert_eq!(
+            availability.shift_rule(1, 2, Duration::hours(1)),
+            Err(AvailabilityError::RuleNotFound {
+                priority: 1,
+                index: 2
+            })
+        );
+        assert_eq!(
+            availability.shift_rule(5, 0, Duration::hours(1)),
+            Err(AvailabilityError::RuleNotFound {
+                priority: 5,
+                index: 0
+            })
+        );
+        assert_eq!(
+            availability.shift_rule(0, 0, Duration::hours(1)),
+            Err(AvailabilityError::ReservedPriority)
+        );
+
+        // Shifting a relative rule onto an identical one keeps both rules
+        let mut availability: Availability<Value> = Availability::new();
+        let week = |start: &str, end: &str| {
+            RuleBuilder::new()
+                .start_time_str(start)
+                .end_time_str(end)
+                .weekdays(&["monday"])
+                .build()
+                .unwrap()
+        };
+        availability
+            .add_rule(week("240101090000", "240131170000"), 1)
+            .unwrap();
+        availability
+            .add_rule(week("240205090000", "240306170000"), 1)
+            .unwrap();
+        assert_eq!(
+            availability.shift_rule(1, 1, -Duration::days(35)),
+            Err(AvailabilityError::DuplicateRule(1))
+        );
+        assert_eq!(availability.rules[1].len(), 2);
+        assert_eq!(
+            availability.rules[1][1].start,
+            create_datetime(2024, 2, 5, 9, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_weekly_summary() {
+        let mut availability: Availability<Value> = Availability::new();
+        let weekdays = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("241231170000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+            .build()
+            .unwrap();
+        let saturday = RuleBuilder::new()
+            .start_time_str("240101100000")
+            .end_time_str("241231140000")
+            .saturday()
+            .build()
+            .unwrap();
+        availability.add_rule(weekdays, 1).unwrap();
+        availability.add_rule(saturday, 1).unwrap();
+
+        assert_eq!(
+            availability.weekly_summary().unwrap(),
+            "Mon-Fri 9–5, Sat 10–2, Sun closed"
+        );
+
+        // A one-off closure breaks the weekly pattern
+        let closure = Rule::new(
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            create_datetime(2024, 1, 1, 13, 0, 0),
+            None,
+            true,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(closure, 2).unwrap();
+        assert!(availability.weekly_summary().is_none());
+
+        let empty: Availability<Value> = Availability::new();
+        assert!(empty.weekly_summary().is_none());
+    }
+
+    #[test]
+    fn test_iso_queries() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            Some(json!("open")),
+        )
+        .unwrap();
+        availability.add_rule(open, 1).unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+
+        assert_eq!(availability.is_open_iso("2024-01-01T09:00:00"), Ok(true));
+        assert_eq!(availability.is_open_iso("2024-01-01T08:59:59Z"), Ok(false));
+        // Taken as written, not converted to UTC 07:00
+        assert_eq!(
+            availability.is_open_iso("2024-01-01T09:00:00+02:00"),
+            Ok(true)
+        );
+        assert!(matches!(
+            availability.is_open_iso("2024-01-01 09:00:00"),
+            Err(AvailabilityError::InvalidDatetime(_))
+        ));
+
+        let frame = availability.get_frame_iso("2024-01-01T12:00:00").unwrap();
+        assert_eq!(frame.payload, Some(json!("open")));
+        assert!(availability.get_frame_iso("noon").is_none());
+    }
+
+    #[test]
+    fn test_coalesce_across_midnight() {
+        let mut availability: Availability<Value> = Availability::new();
+        // Open around the clock Monday to Wednesday, which is expanded per day
+        let rule = RuleBuilder::new()
+            .start_time_str("240101000000")
+            .end_time_str("240104000000")
+            .weekdays(&["mon", "tue", "wed"])
+            .payload(json!("open"))
+            .build()
+            .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 4, 0, 0, 0);
+        availability.to_frames_in_range(start, end);
+        assert_eq!(availability.frames.len(), 3);
+
+        availability.coalesce_across_midnight();
+        assert_eq!(availability.frames.len(), 1);
+        assert_eq!(availability.frames[0].start, start);
+        assert_eq!(availability.frames[0].end, end);
+        assert_eq!(availability.frames[0].payload, Some(json!("open")));
+    }
+
+    #[test]
+    fn test_flatten_rules() {
+        let mut availability: Availability<Value> = Availability::new();
+        for (priority, day) in [(1, 1), (1, 2), (3, 1)] {
+            let rule = Rule::new(
+                create_datetime(2024, 1, day, 9, 0, 0),
+                create_datetime(2024, 1, day, 17, 0, 0),
+                None,
+                false,
+                None,
+            )
+            .unwrap();
+            availability.add_rule(rule, priority).unwrap();
+        }
+
+        let priorities: Vec<usize> = availability
+            .flatten_rules(false)
+            .iter()
+            .map(|(priority, _)| *priority)
+            .collect();
+        assert_eq!(priorities, vec![1, 1, 3]);
+
+        let with_base = availability.flatten_rules(true);
+        assert_eq!(with_base.len(), 4);
+        assert_eq!(with_base[0].0, 0);
+        assert!(with_base[0].1.off);
+    }
+
+    #[test]
+    fn test_set_payload_at() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            Some(json!("old")),
+        )
+        .unwrap();
+        availability.add_rule(open, 1).unwrap();
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+        availability.to_frames_in_range(start, end);
+
+        availability
+            .set_payload_at(1, 0, Some(json!("new")))
+            .unwrap();
+        assert!(availability.frames.is_empty());
+
+        availability.to_frames_in_range(start, end);
+        let frame = availability
+            .get_frame(create_datetime(2024, 1, 1, 12, 0, 0))
+            .unwrap();
+        assert_eq!(frame.payload, Some(json!("new")));
+
+        assert_eq!(
+            availability.set_payload_at(0, 0, None),
+            Err(AvailabilityError::ReservedPriority)
+        );
+        assert_eq!(
+            availability.set_payload_at(1, 1, None),
+            Err(AvailabilityError::RuleNotFound {
+                priority: 1,
+                index: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_next_open_and_closed() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(open, 1).unwrap();
+
+        // Open now, closing at 17:00
+        let now = create_datetime(2024, 1, 1, 12, 0, 0);
+        assert_eq!(
+            availability.next_closed(now),
+            Some(create_datetime(2024, 1, 1, 17, 0, 0))
+        );
+        assert_eq!(availability.next_open(now), Some(now));
+
+        let morning = create_datetime(2024, 1, 1, 6, 0, 0);
+        assert_eq!(
+            availability.next_open(morning),
+            Some(create_datetime(2024, 1, 1, 9, 0, 0))
+        );
+        assert_eq!(availability.next_closed(morning), Some(morning));
+
+        // Never opens again
+        assert_eq!(
+            availability.next_open(create_datetime(2024, 1, 2, 0, 0, 0)),
+            None
+        );
+
+        // Open by default again once the last rule ends
+        let maintenance = (
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 8, 0, 0, 0),
+        );
+        let availability = Availability::<Value>::always_open_except(&[maintenance]);
+        assert_eq!(
+            availability.next_open(create_datetime(2024, 1, 2, 0, 0, 0)),
+            Some(maintenance.1)
+        );
+        let later = create_datetime(2024, 1, 9, 0, 0, 0);
+        assert_eq!(availability.next_open(later), Some(later));
+        assert_eq!(availability.next_closed(later), None);
+    }
+
+    #[test]
+    fn test_any_open() {
+        let provider = |from: u32, to: u32, name: &str| {
+            let mut availability: Availability<Value> = Availability::new();
+            let rule = Rule::new(
+                create_datetime(2024, 1, 1, from, 0, 0),
+                create_datetime(2024, 1, 1, to, 0, 0),
+                None,
+                false,
+                Some(json!(name)),
+            )
+            .unwrap();
+            availability.add_rule(rule, 1).unwrap();
+            availability
+        };
+        // Together the providers cover the whole day
+        let night = provider(0, 9, "night");
+        let day = provider(8, 17, "day");
+        let mut evening = provider(17, 23, "evening");
+        evening
+            .open_until(create_datetime(2024, 1, 1, 23, 0, 0), 2)
+            .unwrap();
+
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+        let frames = Availability::any_open(&[&night, &day, &evening], start, end);
+        let actual: Vec<(NaiveDateTime, NaiveDateTime, Option<Value>)> = frames
+            .iter()
+            .map(|frame| (frame.start, frame.end, frame.payload.clone()))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    start,
+                    create_datetime(2024, 1, 1, 9, 0, 0),
+                    Some(json!("night"))
+                ),
+                (
+                    create_datetime(2024, 1, 1, 9, 0, 0),
+                    create_datetime(2024, 1, 1, 17, 0, 0),
+                    Some(json!("day"))
+                ),
+                (
+                    create_datetime(2024, 1, 1, 17, 0, 0),
+                    create_datetime(2024, 1, 1, 23, 0, 0),
+                    Some(json!("evening"))
+                ),
+                (create_datetime(2024, 1, 1, 23, 0, 0), end, None),
+            ]
+        );
+        assert!(frames.iter().all(|frame| frame.is_on()));
+    }
+
+    #[test]
+    fn test_all_open() {
+        let provider = |from: u32, to: u32| {
+            let mut availability: Availability<Value> = Availability::new();
+            let rule = Rule::new(
+                create_datetime(2024, 1, 1, from, 0, 0),
+                create_datetime(2024, 1, 1, to, 0, 0),
+                None,
+                false,
+                None,
+            )
+            .unwrap();
+            availability.add_rule(rule, 1).unwrap();
+            availability
+        };
+        let first = provider(8, 13);
+        let second = provider(12, 17);
+        let third = provider(9, 15);
+
+        let frames = Availability::all_open(
+            &[&first, &second, &third],
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+        let actual: Vec<(NaiveDateTime, NaiveDateTime)> = frames
+            .iter()
+            .map(|frame| (frame.start, frame.end))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![(
+                create_datetime(2024, 1, 1, 12, 0, 0),
+                create_datetime(2024, 1, 1, 13, 0, 0)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_trim_rules_to_range() {
+        let mut availability: Availability<Value> = Availability::new();
+        let year = Rule::new(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2025, 1, 1, 0, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        let weekly = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("241231170000")
+            .monday()
+            .build()
+            .unwrap();
+        let outside = Rule::new(
+            create_datetime(2024, 6, 1, 0, 0, 0),
+            create_datetime(2024, 6, 2, 0, 0, 0),
+            None,
+            true,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(year, 1).unwrap();
+        availability.add_rule(weekly, 2).unwrap();
+        availability.add_rule(outside, 3).unwrap();
+
+        let start = create_datetime(2024, 3, 1, 0, 0, 0);
+        let end = create_datetime(2024, 4, 1, 0, 0, 0);
+        availability.trim_rules_to_range(start, end);
+
+        assert_eq!(availability.rules.len(), 3);
+        assert_eq!(availability.rules[1][0].start, start);
+        assert_eq!(availability.rules[1][0].end, end);
+        // The relative rule keeps its time window
+        assert_eq!(
+            availability.rules[2][0].start,
+            create_datetime(2024, 3, 1, 9, 0, 0)
+        );
+        assert_eq!(
+            availability.rules[2][0].end,
+            create_datetime(2024, 3, 31, 17, 0, 0)
+        );
+        // The base rule is left intact
+        assert_eq!(availability.rules[0][0].start.year(), 2000);
+    }
+
+    #[test]
+    fn test_trim_rules_to_single_day() {
+        let mut availability: Availability<Value> = Availability::new();
+        let weekdays = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240131170000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+            .build()
+            .unwrap();
+        let saturdays = RuleBuilder::new()
+            .start_time_str("240101100000")
+            .end_time_str("240131140000")
+            .saturday()
+            .build()
+            .unwrap();
+        availability.add_rule(weekdays, 1).unwrap();
+        availability.add_rule(saturdays, 2).unwrap();
+
+        // 2024-01-03 is a Wednesday
+        let start = create_datetime(2024, 1, 3, 0, 0, 0);
+        let end = create_datetime(2024, 1, 4, 0, 0, 0);
+        availability.trim_rules_to_range(start, end);
+
+        // The weekday rule becomes an absolute rule for that day's window, the Saturday
+        // rule has no window left
+        assert_eq!(availability.rules.len(), 2);
+        let rule = &availability.rules[1][0];
+        assert!(rule.is_absolute());
+        assert_eq!(rule.start, create_datetime(2024, 1, 3, 9, 0, 0));
+        assert_eq!(rule.end, create_datetime(2024, 1, 3, 17, 0, 0));
+
+        // Frames and is_open agree
+        availability.to_frames_in_range(start, end);
+        let open = create_datetime(2024, 1, 3, 10, 0, 0);
+        assert!(availability.is_open(open));
+        assert!(availability.get_frame(open).unwrap().is_on());
+        assert_eq!(availability.frames.len(), 3);
+    }
+
+    #[test]
+    fn test_equal_rules() {
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            Some(json!("open")),
+        )
+        .unwrap();
+        let mut first: Availability<Value> = Availability::new();
+        let mut second: Availability<Value> = Availability::new();
+        first.add_rule(open.clone(), 1).unwrap();
+        second.add_rule(open.clone(), 1).unwrap();
+
+        // The base state is not compared
+        second.rules[0][0].off = false;
+        assert!(first.equal_rules(&second));
+
+        let mut other_payload = open;
+        other_payload.payload = Some(json!("other"));
+        second.add_rule(other_payload, 2).unwrap();
+        assert!(!first.equal_rules(&second));
+        assert!(!second.equal_rules(&first));
+    }
+
+    #[test]
+    fn test_always_open_except() {
+        let first = (
+            create_datetime(2024, 1, 1, 2, 0, 0),
+            create_datetime(2024, 1, 1, 4, 0, 0),
+        );
+        let second = (
+            create_datetime(2024, 1, 1, 22, 0, 0),
+            create_datetime(2024, 1, 1, 23, 0, 0),
+        );
+        let mut availability: Availability<Value> =
+            Availability::always_open_except(&[second, first]);
+        assert!(!availability.is_base_off());
+
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+        availability.to_frames_in_range(start, end);
+        let frames: Vec<(NaiveDateTime, NaiveDateTime, bool)> = availability
+            .frames
+            .iter()
+            .map(|frame| (frame.start, frame.end, frame.off))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                (start, first.0, false),
+                (first.0, first.1, true),
+                (first.1, second.0, false),
+                (second.0, second.1, true),
+                (second.1, end, false),
+            ]
+        );
+        assert!(availability.is_open(create_datetime(2030, 6, 1, 12, 0, 0)));
+    }
+
+    #[test]
+    fn test_always_open_except_outside_base_range() {
+        let base_rule = Rule::<Value>::base_rule();
+        let before = (
+            create_datetime(1999, 12, 1, 0, 0, 0),
+            create_datetime(2000, 1, 2, 0, 0, 0),
+        );
+        let after = (
+            create_datetime(2999, 12, 31, 0, 0, 0),
+            create_datetime(3000, 2, 1, 0, 0, 0),
+        );
+        let entirely_before = (
+            create_datetime(1999, 1, 1, 0, 0, 0),
+            create_datetime(1999, 2, 1, 0, 0, 0),
+        );
+        let availability: Availability<Value> =
+            Availability::always_open_except(&[before, after, entirely_before]);
+
+        // Windows are clipped to the base rule, and dropped if nothing is left
+        let windows: Vec<(NaiveDateTime, NaiveDateTime)> = availability.rules[1]
+            .iter()
+            .map(|rule| (rule.start, rule.end))
+            .collect();
+        assert_eq!(
+            windows,
+            vec![(base_rule.start, before.1), (after.0, base_rule.end)]
+        );
+        assert!(!availability.is_open(base_rule.start));
+        assert!(availability.is_open(before.1));
+        assert!(!availability.is_open(after.0));
+    }
+
+    #[test]
+    fn test_include_end_marker() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(open, 1).unwrap();
+        let start = create_datetime(2024, 1, 1, 9, 0, 0);
+        let end = create_datetime(2024, 1, 1, 17, 0, 0);
+
+        availability.to_frames_in_range(start, end);
+        assert_eq!(availability.frames.len(), 1);
+
+        availability.set_include_end_marker(true);
+        availability.to_frames_in_range(start, end);
+        assert_eq!(availability.frames.len(), 2);
+        let marker = &availability.frames[1];
+        assert!(marker.is_end_marker());
+        assert_eq!((marker.start, marker.end), (end, end));
+        assert_eq!(marker.duration(), Duration::zero());
+        // Closed at the end of the range
+        assert!(marker.is_off());
+
+        availability.merge_adjacent_off_gaps();
+        availability
+            .frames
+            .retain(|frame| frame.duration() > Duration::zero() || frame.is_end_marker());
+        assert!(availability.frames[1].is_end_marker());
+    }
+
+    #[test]
+    fn test_rule_at() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        let closure = Rule::new(
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            create_datetime(2024, 1, 1, 13, 0, 0),
+            None,
+            true,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(open, 1).unwrap();
+        availability.add_rule(closure, 2).unwrap();
+
+        assert_eq!(
+            availability.rule_at(create_datetime(2024, 1, 1, 12, 30, 0)),
+            Some((2, 0))
+        );
+        assert_eq!(
+            availability.rule_at(create_datetime(2024, 1, 1, 10, 0, 0)),
+            Some((1, 0))
+        );
+        // Only the base rule applies
+        assert_eq!(
+            availability.rule_at(create_datetime(2024, 1, 1, 18, 0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_open_windows() {
+        let windows = [
+            (
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 12, 0, 0),
+            ),
+            (
+                create_datetime(2024, 1, 2, 9, 0, 0),
+                create_datetime(2024, 1, 2, 12, 0, 0),
+            ),
+            (
+                create_datetime(2024, 1, 3, 9, 0, 0),
+                create_datetime(2024, 1, 3, 12, 0, 0),
+            ),
+        ];
+        let mut availability: Availability<Value> =
+            Availability::from_open_windows(&windows, 1).unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 4, 0, 0, 0),
+        );
+        let open: Vec<(NaiveDateTime, NaiveDateTime)> = availability
+            .frames
+            .iter()
+            .filter(|frame| frame.is_on())
+            .map(|frame| (frame.start, frame.end))
+            .collect();
+        assert_eq!(open, windows);
+
+        // Overlapping windows are rejected
+        let overlapping = [windows[0], windows[0]];
+        assert!(matches!(
+            Availability::<Value>::from_open_windows(&overlapping, 1),
+            Err(AvailabilityError::AbsoluteOverlap { .. })
+        ));
+    }
+
+    #[test]
+    fn test_open_and_close_window() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                1,
+            )
+            .unwrap();
+        availability
+            .close_window(
+                create_datetime(2024, 1, 1, 12, 0, 0),
+                create_datetime(2024, 1, 1, 13, 0, 0),
+                2,
+            )
+            .unwrap();
+
+        assert!(availability.is_open(create_datetime(2024, 1, 1, 10, 0, 0)));
+        assert!(!availability.is_open(create_datetime(2024, 1, 1, 12, 30, 0)));
+        assert!(availability.rules[2][0].off);
+
+        // Overlaps at the same priority are rejected as usual
+        assert!(availability
+            .open_window(
+                create_datetime(2024, 1, 1, 16, 0, 0),
+                create_datetime(2024, 1, 1, 18, 0, 0),
+                1,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let mut availability: Availability<Value> = Availability::new();
+        // Open Monday to Friday 09:00-17:00 in the week starting Monday 2024-01-01
+        let rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240107170000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+            .build()
+            .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+
+        let heatmap = availability.heatmap(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 8, 0, 0, 0),
+        );
+        for (weekday, hours) in heatmap.iter().enumerate() {
+            for (hour, open) in hours.iter().enumerate() {
+                let expected = match weekday < 5 && (9..17).contains(&hour) {
+                    true => Duration::hours(1),
+                    false => Duration::zero(),
+                };
+                assert_eq!(*open, expected, "weekday {} hour {}", weekday, hour);
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_versioning() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                1,
+            )
+            .unwrap();
+
+        let json = availability.to_json().unwrap();
+        let mut value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], json!(1));
+
+        let restored: Availability<Value> = Availability::from_json(&json).unwrap();
+        assert!(restored.equal_rules(&availability));
+        assert!(restored.is_open(create_datetime(2024, 1, 1, 12, 0, 0)));
+
+        value["version"] = json!(999);
+        assert_eq!(
+            Availability::<Value>::from_json(&value.to_string()).err(),
+            Some(AvailabilityError::UnsupportedVersion(999))
+        );
+
+        value.as_object_mut().unwrap().remove("version");
+        assert!(matches!(
+            Availability::<Value>::from_json(&value.to_string()),
+            Err(AvailabilityError::InvalidJson(_))
+        ));
+
+        // A version 1 blob holding rules `add_rule` rejects is rejected as well
+        value["version"] = json!(1);
+        let rule = value["rules"][1][0].clone();
+        value["rules"][1].as_array_mut().unwrap().push(rule);
+        value["rules"][1][1]["start"] = json!("2024-01-01T12:00:00");
+        value["rules"][1][1]["end"] = json!("2024-01-01T18:00:00");
+        assert!(matches!(
+            Availability::<Value>::from_json(&value.to_string()),
+            Err(AvailabilityError::AbsoluteOverlap { priority: 1, .. })
+        ));
+        value["rules"][1][1]["start"] = json!("2024-01-02T18:00:00");
+        assert_eq!(
+            Availability::<Value>::from_json(&value.to_string()).err(),
+            Some(AvailabilityError::StartNotBeforeEnd)
+        );
+    }
+
+    #[test]
+    fn test_timezone_label() {
+        let availability: Availability<Value> =
+            Availability::new().with_timezone_label("Europe/Stockholm");
+        assert_eq!(availability.timezone_label(), Some("Europe/Stockholm"));
+        assert!(availability
+            .to_string()
+            .starts_with("Timezone: Europe/Stockholm\n"));
+
+        let json = availability.to_json().unwrap();
+        let restored: Availability<Value> = Availability::from_json(&json).unwrap();
+        assert_eq!(restored.timezone_label(), Some("Europe/Stockholm"));
+
+        // Unlabeled schedules leave the label out
+        let json = Availability::<Value>::new().to_json().unwrap();
+        assert!(!json.contains("timezone_label"));
+    }
+
+    #[test]
+    fn test_rules_overlapping_rule() {
+        let mut availability: Availability<Value> = Availability::new();
+        for day in 1..=3 {
+            availability
+                .open_window(
+                    create_datetime(2024, 1, day, 9, 0, 0),
+                    create_datetime(2024, 1, day, 17, 0, 0),
+                    1,
+                )
+                .unwrap();
+        }
+
+        let candidate = Rule::new(
+            create_datetime(2024, 1, 2, 16, 0, 0),
+            create_datetime(2024, 1, 2, 18, 0, 0),
+            None,
+            true,
+            None,
+        )
+        .unwrap();
+        let overlapping = availability.rules_overlapping_rule(&candidate, 1);
+        assert_eq!(overlapping.len(), 1);
+        assert_eq!(overlapping[0].0, 1);
+        assert_eq!(overlapping[0].1.start, create_datetime(2024, 1, 2, 9, 0, 0));
+
+        assert!(availability
+            .rules_overlapping_rule(&candidate, 2)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_block_frame() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                1,
+            )
+            .unwrap();
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+        availability.to_frames_in_range(start, end);
+
+        let open = availability
+            .get_frame(create_datetime(2024, 1, 1, 12, 0, 0))
+            .unwrap();
+        assert!(open.is_on());
+        availability.block_frame(&open, 2).unwrap();
+
+        availability.to_frames_in_range(start, end);
+        assert!(availability.frames.iter().all(|frame| frame.is_off()));
+        assert!(!availability.is_open(create_datetime(2024, 1, 1, 12, 0, 0)));
+    }
+
+    #[test]
+    fn test_distinct_payloads() {
+        let mut availability: Availability<Value> = Availability::new();
+        for (day, payload) in [(1, "staffed"), (2, "self-service"), (3, "staffed")] {
+            let rule = Rule::new(
+                create_datetime(2024, 1, day, 9, 0, 0),
+                create_datetime(2024, 1, day, 17, 0, 0),
+                None,
+                false,
+                Some(json!(payload)),
+            )
+            .unwrap();
+            availability.add_rule(rule, 1).unwrap();
+        }
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 4, 0, 0, 0),
+        );
+
+        // Base gaps without payload are left out
+        assert_eq!(
+            availability.distinct_payloads(),
+            vec![json!("staffed"), json!("self-service")]
+        );
+    }
+
+    #[test]
+    fn test_all_absolute_fast_path_matches_general_path() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 3, 17, 0, 0),
+                1,
+            )
+            .unwrap();
+        availability
+            .close_window(
+                create_datetime(2024, 1, 2, 12, 0, 0),
+                create_datetime(2024, 1, 2, 13, 0, 0),
+                2,
+            )
+            .unwrap();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 4, 9, 0, 0),
+                create_datetime(2024, 1, 4, 17, 0, 0),
+                3,
+            )
+            .unwrap();
+
+        let start = create_datetime(2024, 1, 1, 12, 0, 0);
+        let end = create_datetime(2024, 1, 5, 0, 0, 0);
+        let as_tuples = |frames: Vec<Frame<Value>>| {
+            frames
+                .into_iter()
+                .map(|frame| (frame.start, frame.end, frame.off, frame.payload))
+                .collect::<Vec<_>>()
+        };
+        let fast = availability
+            .sweep_frames(start, end, &|_, _| false, true, false, None)
+            .unwrap();
+        let general = availability
+            .sweep_frames(start, end, &|_, _| false, false, false, None)
+            .unwrap();
+        assert_eq!(fast.len(), 6);
+        assert_eq!(as_tuples(fast), as_tuples(general));
+    }
+
+    #[test]
+    fn test_orphan_priorities() {
+        let mut availability: Availability<Value> = Availability::new();
+        for priority in 1..=3 {
+            availability
+                .open_window(
+                    create_datetime(2024, 1, priority as u32, 9, 0, 0),
+                    create_datetime(2024, 1, priority as u32, 17, 0, 0),
+                    priority,
+                )
+                .unwrap();
+        }
+        assert!(availability.validate_no_orphan_priorities().is_empty());
+
+        availability.remove_rule_by_index(2, 0).unwrap();
+        assert_eq!(availability.validate_no_orphan_priorities(), vec![2]);
+
+        availability.compact_priorities();
+        assert!(availability.validate_no_orphan_priorities().is_empty());
+        assert_eq!(availability.rules.len(), 3);
+        assert_eq!(
+            availability.rules[2][0].start,
+            create_datetime(2024, 1, 3, 9, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_open_frames_between() {
+        let mut availability: Availability<Value> = Availability::new();
+        for (from, to) in [(8, 10), (11, 13), (14, 16)] {
+            availability
+                .open_window(
+                    create_datetime(2024, 1, 1, from, 0, 0),
+                    create_datetime(2024, 1, 1, to, 0, 0),
+                    1,
+                )
+                .unwrap();
+        }
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+
+        let open = availability.open_frames_between(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 12, 0, 0),
+        );
+        let actual: Vec<(NaiveDateTime, NaiveDateTime)> =
+            open.iter().map(|frame| (frame.start, frame.end)).collect();
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    create_datetime(2024, 1, 1, 9, 0, 0),
+                    create_datetime(2024, 1, 1, 10, 0, 0)
+                ),
+                (
+                    create_datetime(2024, 1, 1, 11, 0, 0),
+                    create_datetime(2024, 1, 1, 12, 0, 0)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collapse_to_states() {
+        let mut availability: Availability<Value> = Availability::new();
+        let open_rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101170000")
+            .payload(json!("morning"))
+            .build()
+            .unwrap();
+        availability.add_rule(open_rule, 1).unwrap();
+        let payload_rule = RuleBuilder::new()
+            .start_time_str("240101120000")
+            .end_time_str("240101170000")
+            .payload(json!("afternoon"))
+            .build()
+            .unwrap();
+        availability.add_rule(payload_rule, 2).unwrap();
+
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let states = availability.collapse_to_states(start, create_datetime(2024, 1, 2, 0, 0, 0));
+        assert_eq!(
+            states,
+            vec![
+                (start, false),
+                (create_datetime(2024, 1, 1, 9, 0, 0), true),
+                (create_datetime(2024, 1, 1, 17, 0, 0), false),
+            ]
+        );
+
+        // The initial state is kept even when the range starts inside a frame
+        let start = create_datetime(2024, 1, 1, 10, 0, 0);
+        let states = availability.collapse_to_states(start, create_datetime(2024, 1, 1, 18, 0, 0));
+        assert_eq!(
+            states,
+            vec![
+                (start, true),
+                (create_datetime(2024, 1, 1, 17, 0, 0), false)
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn test_from_toml() {
+        let toml = r#"
+            monday = ["09:00-12:00", "13:00-17:00"]
+            tuesday = ["09:00-12:00"]
+            saturday = ["22:00-24:00"]
+        "#;
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(); // Monday
+        let until = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let mut availability: Availability<Value> =
+            Availability::from_toml(toml, from, until, 1).unwrap();
+
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 8, 0, 0, 0),
+        );
+        let open: Vec<(NaiveDateTime, NaiveDateTime)> = availability
+            .frames()
+            .iter()
+            .filter(|frame| frame.is_on())
+            .map(|frame| (frame.start, frame.end))
+            .collect();
+        assert_eq!(
+            open,
+            vec![
+                (
+                    create_datetime(2024, 1, 1, 9, 0, 0),
+                    create_datetime(2024, 1, 1, 12, 0, 0)
+                ),
+                (
+                    create_datetime(2024, 1, 1, 13, 0, 0),
+                    create_datetime(2024, 1, 1, 17, 0, 0)
+                ),
+                (
+                    create_datetime(2024, 1, 2, 9, 0, 0),
+                    create_datetime(2024, 1, 2, 12, 0, 0)
+                ),
+                (
+                    create_datetime(2024, 1, 6, 22, 0, 0),
+                    create_datetime(2024, 1, 7, 0, 0, 0)
+                ),
+            ]
+        );
+
+        // Nothing is open after `until`
+        assert!(!availability.is_open(create_datetime(2024, 2, 5, 10, 0, 0)));
+
+        // The second Monday window is one priority up, so every priority satisfies
+        // `add_rule`
+        assert_eq!(availability.rule_count_at(1), 2);
+        assert_eq!(availability.rule_count_at(2), 1);
+        let mut rebuilt: Availability<Value> = Availability::new();
+        for (priority, rule) in availability.flatten_rules(false) {
+            rebuilt.add_rule(rule, priority).unwrap();
+        }
+        assert!(rebuilt.equal_rules(&availability));
+
+        let overlapping = "monday = [\"09:00-12:00\", \"11:00-13:00\"]";
+        assert!(Availability::<Value>::from_toml(overlapping, from, until, 1).is_err());
+        let unknown_day = "someday = [\"09:00-12:00\"]";
+        assert!(Availability::<Value>::from_toml(unknown_day, from, until, 1).is_err());
+    }
+
+    #[test]
+    fn test_add_changes_frames() {
+        let mut availability: Availability<Value> = Availability::new();
+        let closed = RuleBuilder::new()
+            .start_time_str("240101080000")
+            .end_time_str("240101180000")
+            .off(true)
+            .build()
+            .unwrap();
+        availability.add_rule(closed, 2).unwrap();
+
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+        let shadowed = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101170000")
+            .build()
+            .unwrap();
+        assert!(!availability.add_changes_frames(shadowed, 1, start, end));
+
+        let partly_visible = RuleBuilder::new()
+            .start_time_str("240101170000")
+            .end_time_str("240101190000")
+            .build()
+            .unwrap();
+        assert!(availability.add_changes_frames(partly_visible, 1, start, end));
+
+        // An "on" rule would reopen the closure, but `add_rule` rejects it in closed
+        // exceptions mode
+        let mut closed_exceptions: Availability<Value> = Availability::closed_exceptions_mode();
+        closed_exceptions
+            .close_window(
+                create_datetime(2024, 1, 1, 8, 0, 0),
+                create_datetime(2024, 1, 1, 18, 0, 0),
+                1,
+            )
+            .unwrap();
+        let reopen = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101170000")
+            .build()
+            .unwrap();
+        assert!(closed_exceptions.add_rule(reopen.clone(), 2).is_err());
+        assert!(!closed_exceptions.add_changes_frames(reopen, 2, start, end));
+    }
+
+    #[test]
+    fn test_open_day_count() {
+        let mut availability: Availability<Value> = Availability::new();
+        let weekdays = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("241231170000")
+            .weekdays(&["monday", "tuesday", "wednesday", "thursday", "friday"])
+            .build()
+            .unwrap();
+        availability.add_rule(weekdays, 1).unwrap();
+
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 8).unwrap();
+        let next_monday = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        assert_eq!(availability.open_day_count(monday, next_monday), 5);
+        assert_eq!(availability.open_day_count(next_monday, monday), 0);
+    }
+
+    #[test]
+    fn test_freeze() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                1,
+            )
+            .unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+
+        // Mutators are not reachable, see the compile_fail example on `FrozenAvailability`
+        let frozen = availability.freeze();
+        assert!(frozen.is_open(create_datetime(2024, 1, 1, 10, 0, 0)));
+        assert!(!frozen.is_open(create_datetime(2024, 1, 1, 18, 0, 0)));
+        assert_eq!(frozen.frames().len(), 3);
+    }
+
+    #[test]
+    fn test_open_seconds_on() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                1,
+            )
+            .unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert_eq!(availability.open_seconds_on(date), 28800);
+        assert_eq!(availability.open_seconds_on(date.succ_opt().unwrap()), 0);
+    }
+
+    #[test]
+    fn test_forever_sunday_off_rule() {
+        let mut availability: Availability<Value> = Availability::new();
+        let always_open = RuleBuilder::new().forever().build().unwrap();
+        availability.add_rule(always_open, 1).unwrap();
+        let sundays_closed = RuleBuilder::new()
+            .forever()
+            .sunday()
+            .off(true)
+            .build()
+            .unwrap();
+        availability.add_rule(sundays_closed, 2).unwrap();
+
+        // Wednesday to Wednesday, covering the Sundays 2024-01-07 and 2024-01-14
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 3, 0, 0, 0),
+            create_datetime(2024, 1, 17, 0, 0, 0),
+        );
+        let closed: Vec<(NaiveDateTime, NaiveDateTime)> = availability
+            .frames()
+            .iter()
+            .filter(|frame| frame.is_off())
+            .map(|frame| (frame.start, frame.end))
+            .collect();
+        assert_eq!(
+            closed,
+            vec![
+                (
+                    create_datetime(2024, 1, 7, 0, 0, 0),
+                    create_datetime(2024, 1, 8, 0, 0, 0)
+                ),
+                (
+                    create_datetime(2024, 1, 14, 0, 0, 0),
+                    create_datetime(2024, 1, 15, 0, 0, 0)
+                ),
+            ]
+        );
+        assert!(availability.is_open(create_datetime(2024, 1, 6, 23, 59, 59)));
+        assert!(!availability.is_open(create_datetime(2024, 1, 7, 12, 0, 0)));
+        assert!(availability.is_open(create_datetime(2024, 1, 8, 0, 0, 0)));
+    }
+
+    #[test]
+    fn test_rule_count_at_and_priorities() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 12, 0, 0),
+                1,
+            )
+            .unwrap();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 13, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                1,
+            )
+            .unwrap();
+        availability
+            .close_window(
+                create_datetime(2024, 1, 1, 10, 0, 0),
+                create_datetime(2024, 1, 1, 11, 0, 0),
+                3,
+            )
+            .unwrap();
+
+        assert_eq!(availability.rule_count_at(0), 1);
+        assert_eq!(availability.rule_count_at(1), 2);
+        assert_eq!(availability.rule_count_at(2), 0);
+        assert_eq!(availability.rule_count_at(3), 1);
+        assert_eq!(availability.rule_count_at(10), 0);
+        assert_eq!(availability.priorities().collect::<Vec<_>>(), vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn test_rule_ending_at_range_end() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                1,
+            )
+            .unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+        );
+        let frames: Vec<(NaiveDateTime, NaiveDateTime, bool)> = availability
+            .frames()
+            .iter()
+            .map(|frame| (frame.start, frame.end, frame.off))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                (
+                    create_datetime(2024, 1, 1, 0, 0, 0),
+                    create_datetime(2024, 1, 1, 9, 0, 0),
+                    true
+                ),
+                (
+                    create_datetime(2024, 1, 1, 9, 0, 0),
+                    create_datetime(2024, 1, 1, 17, 0, 0),
+                    false
+                ),
+            ]
+        );
+
+        // A forever rule ends exactly at the end of the base range
+        let mut availability: Availability<Value> = Availability::new();
+        let always_open = RuleBuilder::new().forever().build().unwrap();
+        let base_end = always_open.end;
+        availability.add_rule(always_open, 1).unwrap();
+        availability.to_frames_in_range(base_end - Duration::days(1), base_end);
+        let frames = availability.frames();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].start, base_end - Duration::days(1));
+        assert_eq!(frames[0].end, base_end);
+        assert!(frames[0].is_on());
+    }
+
+    #[test]
+    fn test_reset_to_base() {
+        let closed = (
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+        );
+        let mut availability: Availability<Value> =
+            Availability::always_open_except(&[closed]).with_timezone_label("Europe/Stockholm");
+        availability.set_include_end_marker(true);
+        availability.set_frame_hook(Box::new(|_| panic!("hook must be reset")));
+
+        availability.reset_to_base();
+        assert!(availability.is_base_off());
+        assert_eq!(availability.rules.len(), 1);
+        assert_eq!(availability.timezone_label(), None);
+
+        // No end marker is appended and the hook is gone
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+        assert_eq!(availability.frames().len(), 1);
+        assert!(availability.frames()[0].is_off());
+    }
+
+    #[test]
+    fn test_frames_to_json() {
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101170000")
+            .payload(json!({"staff": 2}))
+            .build()
+            .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+
+        let json = availability.frames_to_json().unwrap();
+        let frames: Vec<Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(
+            frames[1],
+            json!({
+                "start": "2024-01-01T09:00:00",
+                "end": "2024-01-01T17:00:00",
+                "open": true,
+                "payload": {"staff": 2},
+            })
+        );
+        assert_eq!(frames[2]["open"], json!(false));
+        assert!(frames[2].get("payload").is_none());
+    }
+
+    #[test]
+    fn test_at_or_default() {
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101170000")
+            .payload(json!("staffed"))
+            .build()
+            .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+
+        assert_eq!(
+            availability.at_or_default(create_datetime(2024, 1, 1, 12, 0, 0), json!("none")),
+            json!("staffed")
+        );
+        assert_eq!(
+            availability.at_or_default(create_datetime(2024, 1, 1, 18, 0, 0), json!("none")),
+            json!("none")
+        );
+    }
+
+    #[test]
+    fn test_validate_rule() {
+        let availability: Availability<Value> = Availability::new();
+        let valid = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("241231170000")
+            .weekdays(&["monday"])
+            .build()
+            .unwrap();
+        assert_eq!(availability.validate_rule(&valid), Ok(()));
+
+        let mut empty = valid.clone();
+        empty.end = empty.start;
+        assert_eq!(
+            availability.validate_rule(&empty),
+            Err(AvailabilityError::StartNotBeforeEnd)
+        );
+
+        let mut invalid_weekdays = valid.clone();
+        invalid_weekdays.weekdays = Some(Weekdays::from_bits_retain(0xFF));
+        assert_eq!(
+            availability.validate_rule(&invalid_weekdays),
+            Err(AvailabilityError::InvalidWeekday)
+        );
+
+        let mut before_base = valid.clone();
+        before_base.start = create_datetime(1999, 12, 31, 0, 0, 0);
+        assert!(availability.validate_rule(&before_base).is_err());
+
+        let mut after_base = valid;
+        after_base.end = create_datetime(3000, 1, 1, 0, 0, 1);
+        assert_eq!(
+            availability.validate_rule(&after_base),
+            Err(AvailabilityError::InvalidRule(
+                "Rule must lie within 2000-01-01 00:00:00 and 3000-01-01 00:00:00".to_string()
+            ))
+        );
+
+        // Rule::new and add_rule make the same checks
+        assert!(Rule::<Value>::new(before_base.start, before_base.end, None, false, None).is_err());
+        let mut availability = availability;
+        assert_eq!(
+            availability.add_rule(after_base.clone(), 1),
+            availability.validate_rule(&after_base)
+        );
+        assert_eq!(
+            availability.add_rule(invalid_weekdays, 1),
+            Err(AvailabilityError::InvalidWeekday)
+        );
+        assert_eq!(availability.rule_count_at(1), 0);
+    }
+
+    #[test]
+    fn test_is_open_weekly() {
+        let mut availability: Availability<Value> = Availability::new();
+        let weekdays = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("241231170000")
+            .weekdays(&["monday", "tuesday", "wednesday", "thursday", "friday"])
+            .build()
+            .unwrap();
+        availability.add_rule(weekdays, 1).unwrap();
+
+        let time = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        assert_eq!(
+            availability.is_open_weekly(Weekday::Mon, time(10)),
+            Some(true)
+        );
+        assert_eq!(
+            availability.is_open_weekly(Weekday::Mon, time(18)),
+            Some(false)
+        );
+        assert_eq!(
+            availability.is_open_weekly(Weekday::Sat, time(10)),
+            Some(false)
+        );
+
+        // A holiday closure depends on the date
+        availability
+            .close_window(
+                create_datetime(2024, 12, 25, 0, 0, 0),
+                create_datetime(2024, 12, 26, 0, 0, 0),
+                2,
+            )
+            .unwrap();
+        assert_eq!(
+            availability.is_open_weekly(Weekday::Mon, time(10)),
+            Some(true)
+        );
+        assert_eq!(availability.is_open_weekly(Weekday::Sat, time(10)), None);
+    }
+
+    #[test]
+    fn test_to_freebusy_json() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                1,
+            )
+            .unwrap();
+        availability
+            .close_window(
+                create_datetime(2024, 1, 1, 12, 0, 0),
+                create_datetime(2024, 1, 1, 13, 0, 0),
+                2,
+            )
+            .unwrap();
+
+        let json = availability.to_freebusy_json(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value,
+            json!({"busy": [
+                {"start": "2024-01-01T00:00:00Z", "end": "2024-01-01T09:00:00Z"},
+                {"start": "2024-01-01T12:00:00Z", "end": "2024-01-01T13:00:00Z"},
+                {"start": "2024-01-01T17:00:00Z", "end": "2024-01-02T00:00:00Z"},
+            ]})
+        );
+    }
+
+    #[test]
+    fn test_apply_event() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .apply_event(create_datetime(2024, 1, 1, 9, 0, 0), true, 1)
+            .unwrap();
+        availability
+            .apply_event(create_datetime(2024, 1, 1, 17, 0, 0), false, 1)
+            .unwrap();
+        // A repeated event does not add a rule
+        availability
+            .apply_event(create_datetime(2024, 1, 1, 18, 0, 0), false, 1)
+            .unwrap();
+        assert_eq!(availability.rule_count_at(1), 2);
+
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+        let open: Vec<(NaiveDateTime, NaiveDateTime)> = availability
+            .frames()
+            .iter()
+            .filter(|frame| frame.is_on())
+            .map(|frame| (frame.start, frame.end))
+            .collect();
+        assert_eq!(
+            open,
+            vec![(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0)
+            )]
+        );
+
+        // Reopening continues with a new open rule
+        availability
+            .apply_event(create_datetime(2024, 1, 2, 9, 0, 0), true, 1)
+            .unwrap();
+        assert_eq!(availability.rule_count_at(1), 3);
+        assert!(availability.is_open(create_datetime(2024, 6, 1, 0, 0, 0)));
+        assert!(!availability.is_open(create_datetime(2024, 1, 1, 20, 0, 0)));
+    }
+
+    #[test]
+    fn test_is_open_all_of() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                1,
+            )
+            .unwrap();
+        availability
+            .close_window(
+                create_datetime(2024, 1, 1, 12, 0, 0),
+                create_datetime(2024, 1, 1, 13, 0, 0),
+                2,
+            )
+            .unwrap();
+
+        let morning = (
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 10, 0, 0),
+        );
+        let lunch = (
+            create_datetime(2024, 1, 1, 11, 30, 0),
+            create_datetime(2024, 1, 1, 12, 30, 0),
+        );
+        let afternoon = (
+            create_datetime(2024, 1, 1, 14, 0, 0),
+            create_datetime(2024, 1, 1, 17, 0, 0),
+        );
+        assert!(availability.is_open_all_of(&[afternoon, morning]));
+        assert!(!availability.is_open_all_of(&[afternoon, lunch, morning]));
+        assert!(availability.is_open_all_of(&[]));
+    }
+
+    #[test]
+    fn test_closed_exceptions_mode() {
+        let mut availability: Availability<Value> = Availability::closed_exceptions_mode();
+        assert!(!availability.is_base_off());
+
+        assert_eq!(
+            availability.open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                1,
+            ),
+            Err(AvailabilityError::OnRuleInClosedExceptionsMode)
+        );
+        availability
+            .close_window(
+                create_datetime(2024, 1, 1, 12, 0, 0),
+                create_datetime(2024, 1, 1, 13, 0, 0),
+                1,
+            )
+            .unwrap();
+
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+        let frames: Vec<(NaiveDateTime, NaiveDateTime, bool)> = availability
+            .frames()
+            .iter()
+            .map(|frame| (frame.start, frame.end, frame.off))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                (
+                    create_datetime(2024, 1, 1, 0, 0, 0),
+                    create_datetime(2024, 1, 1, 12, 0, 0),
+                    false
+                ),
+                (
+                    create_datetime(2024, 1, 1, 12, 0, 0),
+                    create_datetime(2024, 1, 1, 13, 0, 0),
+                    true
+                ),
+                (
+                    create_datetime(2024, 1, 1, 13, 0, 0),
+                    create_datetime(2024, 1, 2, 0, 0, 0),
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preview_add_in_closed_exceptions_mode() {
+        let mut availability: Availability<Value> = Availability::closed_exceptions_mode();
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+        let rule = |off: bool| {
+            Rule::new(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                None,
+                off,
+                None,
+            )
+            .unwrap()
+        };
+
+        // Previewing fails exactly like adding
+        assert_eq!(
+            availability.preview_add(rule(false), 1, start, end).err(),
+            Some(AvailabilityError::OnRuleInClosedExceptionsMode)
+        );
+        assert_eq!(
+            availability.add_rule(rule(false), 1),
+            Err(AvailabilityError::OnRuleInClosedExceptionsMode)
+        );
+
+        let preview = availability.preview_add(rule(true), 1, start, end).unwrap();
+        availability.add_rule(rule(true), 1).unwrap();
+        assert_eq!(preview.len(), availability.compute_frames(start, end).len());
+    }
+
+    #[test]
+    fn test_max_open_in_window() {
+        let mut availability: Availability<Value> = Availability::new();
+        for (from, to) in [(8, 10), (11, 17), (18, 19)] {
+            availability
+                .open_window(
+                    create_datetime(2024, 1, 1, from, 0, 0),
+                    create_datetime(2024, 1, 1, to, 0, 0),
+                    1,
+                )
+                .unwrap();
+        }
+
+        let start = create_datetime(2024, 1, 1, 0, 0, 0);
+        let end = create_datetime(2024, 1, 2, 0, 0, 0);
+        assert_eq!(
+            availability.max_open_in_window(Duration::hours(4), start, end),
+            (create_datetime(2024, 1, 1, 11, 0, 0), Duration::hours(4))
+        );
+        // 08:00, 09:00 and 11:00 all give seven hours, the earliest wins
+        assert_eq!(
+            availability.max_open_in_window(Duration::hours(8), start, end),
+            (create_datetime(2024, 1, 1, 8, 0, 0), Duration::hours(7))
+        );
+
+        let closed: Availability<Value> = Availability::new();
+        assert_eq!(
+            closed.max_open_in_window(Duration::hours(4), start, end),
+            (start, Duration::zero())
+        );
+    }
+
+    #[test]
+    fn test_merge_priorities() {
+        let mut availability: Availability<Value> = Availability::new();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 9, 0, 0),
+                create_datetime(2024, 1, 1, 12, 0, 0),
+                1,
+            )
+            .unwrap();
+        availability
+            .open_window(
+                create_datetime(2024, 1, 1, 13, 0, 0),
+                create_datetime(2024, 1, 1, 17, 0, 0),
+                2,
+            )
+            .unwrap();
+        availability
+            .close_window(
+                create_datetime(2024, 1, 1, 10, 0, 0),
+                create_datetime(2024, 1, 1, 11, 0, 0),
+                3,
+            )
+            .unwrap();
+
+        availability.merge_priorities(2, 1).unwrap();
+        assert_eq!(availability.rule_count_at(1), 2);
+        assert_eq!(availability.rule_count_at(2), 0);
+
+        // The closure overlaps the morning window, so nothing moves
+        assert!(matches!(
+            availability.merge_priorities(3, 1),
+            Err(AvailabilityError::AbsoluteOverlap { .. })
+        ));
+        assert_eq!(availability.rule_count_at(1), 2);
+        assert_eq!(availability.rule_count_at(3), 1);
+        assert!(!availability.is_open(create_datetime(2024, 1, 1, 10, 30, 0)));
+
+        assert!(availability.merge_priorities(1, 0).is_err());
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let mut availability: Availability<Value> = Availability::new();
+        let weekdays = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("241231170000")
+            .weekdays(&["monday", "tuesday", "wednesday", "thursday", "friday"])
+            .payload(json!({"staff": 2}))
+            .build()
+            .unwrap();
+        availability.add_rule(weekdays, 1).unwrap();
+        availability
+            .close_window(
+                create_datetime(2024, 1, 3, 12, 0, 0),
+                create_datetime(2024, 1, 3, 13, 0, 0),
+                2,
+            )
+            .unwrap();
+        let (start, end) = (
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 8, 0, 0, 0),
+        );
+        availability.to_frames_in_range(start, end);
+
+        let json = serde_json::to_string(&availability).unwrap();
+        let mut restored: Availability<Value> = serde_json::from_str(&json).unwrap();
+        assert!(restored.equal_rules(&availability));
+        assert!(restored.frames().is_empty());
+
+        restored.to_frames_in_range(start, end);
+        let summarize = |availability: &Availability<Value>| {
+            availability
+                .frames()
+                .iter()
+                .map(|frame| (frame.start, frame.end, frame.off, frame.payload.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(summarize(&restored), summarize(&availability));
+
+        // The serde and to_json formats are the same
+        assert_eq!(json, availability.to_json().unwrap());
+        assert!(!json.contains("closed_exceptions_only"));
+        assert!(!json.contains("include_end_marker"));
+
+        // Settings survive the round trip
+        let mut availability: Availability<Value> = Availability::closed_exceptions_mode();
+        availability.set_include_end_marker(true);
+        let json = serde_json::to_string(&availability).unwrap();
+        let mut restored: Availability<Value> = serde_json::from_str(&json).unwrap();
+        assert!(restored.closed_exceptions_only);
+        assert!(restored.include_end_marker);
+        assert!(!restored.is_base_off());
+        assert_eq!(
+            restored.open_window(start, end, 1),
+            Err(AvailabilityError::OnRuleInClosedExceptionsMode)
+        );
+
+        // Closed exceptions mode must not hold "on" rules
+        let mut availability: Availability<Value> = Availability::new();
+        availability.open_window(start, end, 1).unwrap();
+        let mut value = serde_json::to_value(&availability).unwrap();
+        value["closed_exceptions_only"] = json!(true);
+        assert!(serde_json::from_value::<Availability<Value>>(value).is_err());
+
+        // Rules are validated like `add_rule` does, e.g. an end before the start
+        let mut value = serde_json::to_value(&availability).unwrap();
+        value["rules"][1][0]["end"] = json!("2023-12-31T00:00:00");
+        let result = serde_json::from_value::<Availability<Value>>(value.clone());
+        assert!(result
+            .err()
+            .is_some_and(|error| error.to_string() == "Start must not be after or equal to end"));
+        assert_eq!(
+            Availability::<Value>::from_json(&value.to_string()).err(),
+            Some(AvailabilityError::StartNotBeforeEnd)
+        );
+    }
+
+    #[test]
+    fn test_is_open_str() {
+        let mut availability: Availability<Value> = Availability::new();
+        let weekdays = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("241231170000")
+            .weekdays(&["monday", "wednesday"])
+            .build()
+            .unwrap();
+        availability.add_rule(weekdays, 1).unwrap();
+
+        // 2024-01-01 is a Monday
+        assert_eq!(availability.is_open_str("240101100000"), Some(true));
+        assert_eq!(availability.is_open_str("240101170000"), Some(false));
+        assert_eq!(availability.is_open_str("240102100000"), Some(false));
+        assert_eq!(availability.is_open_str("240103165959"), Some(true));
+        assert_eq!(availability.is_open_str("2024-01-01 10:00:00"), None);
+    }
+
+    #[test]
+    fn test_iter_frames() {
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101170000")
+            .payload(json!({"desk": 1}))
+            .build()
+            .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+
+        let iterated: Vec<&Frame<Value>> = availability.iter_frames().collect();
+        assert_eq!(iterated.len(), availability.frames().len());
+        assert!(iterated
+            .iter()
+            .zip(availability.frames())
+            .all(|(frame, stored)| std::ptr::eq(*frame, stored)));
+        assert!(iterated
+            .windows(2)
+            .all(|pair| pair[0].start <= pair[1].start));
+
+        let mut looped = Vec::new();
+        for frame in &availability {
+            looped.push(frame);
+        }
+        assert_eq!(looped.len(), iterated.len());
+        assert!(looped
+            .iter()
+            .zip(&iterated)
+            .all(|(frame, iterated)| std::ptr::eq(*frame, *iterated)));
+
+        let open: Vec<&Frame<Value>> = availability.iter_frames().filter(|f| f.is_on()).collect();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].start, create_datetime(2024, 1, 1, 9, 0, 0));
+    }
+
+    #[test]
+    fn test_next_transition() {
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101170000")
+            .build()
+            .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+        assert_eq!(
+            availability.next_transition(create_datetime(2024, 1, 1, 10, 0, 0)),
+            None
+        );
+
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 6, 0, 0),
+            create_datetime(2024, 1, 2, 0, 0, 0),
+        );
+        // Inside a frame
+        assert_eq!(
+            availability.next_transition(create_datetime(2024, 1, 1, 10, 0, 0)),
+            Some(create_datetime(2024, 1, 1, 17, 0, 0))
+        );
+        // On a boundary
+        assert_eq!(
+            availability.next_transition(create_datetime(2024, 1, 1, 9, 0, 0)),
+            Some(create_datetime(2024, 1, 1, 17, 0, 0))
+        );
+        // Before the first frame
+        assert_eq!(
+            availability.next_transition(create_datetime(2024, 1, 1, 0, 0, 0)),
+            Some(create_datetime(2024, 1, 1, 6, 0, 0))
+        );
+        // Past the last frame
+        assert_eq!(
+            availability.next_transition(create_datetime(2024, 1, 2, 0, 0, 0)),
+            None
+        );
+
+        // `next_open` and `next_closed` evaluate the rules, regardless of the frames
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 1, 6, 0, 0),
+        );
+        let morning = create_datetime(2024, 1, 1, 7, 0, 0);
+        assert_eq!(availability.next_transition(morning), None);
+        assert_eq!(
+            availability.next_open(morning),
+            Some(create_datetime(2024, 1, 1, 9, 0, 0))
+        );
+        assert_eq!(
+            availability.next_closed(create_datetime(2024, 1, 1, 10, 0, 0)),
+            Some(create_datetime(2024, 1, 1, 17, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_total_open_duration() {
+        // The store schedule from the quick start example
+        let mut availability: Availability<Value> = Availability::new();
+        let weekday_rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240131170000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+            .build()
+            .unwrap();
+        let sale_rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240107200000")
+            .weekdays(&["mon", "tue", "wed", "thu", "fri"])
+            .build()
+            .unwrap();
+        let inventory_rule = RuleBuilder::new()
+            .start_time_str("240105000000")
+            .end_time_str("240106000000")
+            .off(true)
+            .build()
+            .unwrap();
+        availability.add_rule(weekday_rule, 1).unwrap();
+        availability.add_rule(sale_rule, 2).unwrap();
+        availability.add_rule(inventory_rule, 3).unwrap();
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 0, 0, 0),
+            create_datetime(2024, 1, 25, 0, 0, 0),
+        );
+
+        // Jan 1-4 open 9-20, Jan 5 closed, then 13 weekdays open 9-17
+        let open = Duration::hours(4 * 11 + 13 * 8);
+        assert_eq!(availability.total_open_duration(), open);
+        assert_eq!(
+            availability.total_closed_duration(),
+            Duration::days(24) - open
+        );
+
+        // The second week, starting on a Monday
+        assert_eq!(
+            availability.open_duration_in(
+                create_datetime(2024, 1, 8, 0, 0, 0),
+                create_datetime(2024, 1, 15, 0, 0, 0)
+            ),
+            Duration::hours(40)
+        );
+        // Clipped to the middle of a day
+        assert_eq!(
+            availability.open_duration_in(
+                create_datetime(2024, 1, 8, 12, 0, 0),
+                create_datetime(2024, 1, 8, 23, 0, 0)
+            ),
+            Duration::hours(5)
+        );
+    }
+
+    // `Availability` must stay usable across threads, including with a frame hook set
+    fn _assert_send_sync<S: Send + Sync>() {}
+    fn _availability_is_send_sync() {
+        _assert_send_sync::<Availability<Value>>();
+    }
+
+    #[test]
+    fn test_apply_event_in_closed_exceptions_mode() {
+        let mut availability: Availability<Value> = Availability::closed_exceptions_mode();
+        availability
+            .apply_event(create_datetime(2024, 1, 1, 9, 0, 0), false, 1)
+            .unwrap();
+        // Opening ends the closure instead of adding an "on" rule
+        availability
+            .apply_event(create_datetime(2024, 1, 1, 17, 0, 0), true, 1)
+            .unwrap();
+        assert_eq!(availability.rule_count_at(1), 1);
+        assert_eq!(
+            availability.rules[1][0].end,
+            create_datetime(2024, 1, 1, 17, 0, 0)
+        );
+        assert!(!availability.is_open(create_datetime(2024, 1, 1, 12, 0, 0)));
+        assert!(availability.is_open(create_datetime(2024, 1, 1, 18, 0, 0)));
+
+        // Opening while already open changes nothing
+        availability
+            .apply_event(create_datetime(2024, 1, 1, 18, 0, 0), true, 1)
+            .unwrap();
+        availability
+            .apply_event(create_datetime(2024, 1, 1, 18, 0, 0), true, 2)
+            .unwrap();
+        assert_eq!(availability.rule_count_at(1), 1);
+        assert_eq!(availability.rule_count_at(2), 0);
+    }
+
+    #[test]
+    fn test_frame_hook_invoked_per_frame() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let mut availability: Availability<Value> = Availability::new();
+        let rule = Rule::new(
+            create_datetime(2024, 1, 1, 9, 0, 0),
+            create_datetime(2024, 1, 1, 12, 0, 0),
+            None,
+            false,
+            None,
+        )
+        .unwrap();
+        availability.add_rule(rule, 1).unwrap();
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let hook_count = Arc::clone(&count);
+        availability.set_frame_hook(Box::new(move |_frame| {
+            hook_count.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        availability.to_frames_in_range(
+            create_datetime(2024, 1, 1, 8, 0, 0),
+            create_datetime(2024, 1, 1, 13, 0, 0),
+        );
+        assert_eq!(count.load(Ordering::Relaxed), availability.frames.len());
+        assert_eq!(count.load(Ordering::Relaxed), 3);
+    }
 }
@@ -1,2 +1,119 @@
+use chrono::{DateTime, NaiveDateTime, NaiveTime};
+
+use crate::error::AvailabilityError;
+
 pub(crate) const BASE_RULE_YEAR_START: i32 = 2000;
 pub(crate) const BASE_RULE_YEAR_END: i32 = 3000;
+
+/// Datetime format expected by all `_str` methods, i.e. `"YYMMDDHHMMSS"`.
+pub const DATETIME_FORMAT: &str = "%y%m%d%H%M%S";
+
+/// Parses a datetime string in the crate's `"YYMMDDHHMMSS"` format.
+///
+/// Useful to validate input up front, since the `_str` methods silently ignore
+/// strings they cannot parse.
+pub fn parse_crate_datetime(datetime: &str) -> Result<NaiveDateTime, AvailabilityError> {
+    NaiveDateTime::parse_from_str(datetime, DATETIME_FORMAT)
+        .map_err(|_| AvailabilityError::InvalidDatetime(datetime.to_string()))
+}
+
+/// Parses an RFC 3339 / ISO 8601 datetime, e.g. `"2024-01-01T09:00:00+01:00"`.
+///
+/// Schedules use naive wall-clock times, so the datetime is taken as written and any
+/// offset is ignored, i.e. `"2024-01-01T09:00:00+02:00"` is 09:00. The offset may also
+/// be left out, e.g. `"2024-01-01T09:00:00"`.
+pub fn parse_iso_datetime(datetime: &str) -> Result<NaiveDateTime, AvailabilityError> {
+    DateTime::parse_from_rfc3339(datetime)
+        .map(|parsed| parsed.naive_local())
+        .or_else(|_| NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S%.f"))
+        .map_err(|_| AvailabilityError::InvalidDatetime(datetime.to_string()))
+}
+
+/// Parses a daily time window such as `"09:00-17:00"` into its start and end time.
+///
+/// An end of `"24:00"` or `"00:00"` means until midnight and is returned as 00:00, the
+/// same convention rules use. Otherwise the start must be before the end.
+pub fn parse_time_window(window: &str) -> Result<(NaiveTime, NaiveTime), AvailabilityError> {
+    let invalid = || AvailabilityError::InvalidRule(format!("Invalid time window: {}", window));
+    let (start, end) = window.split_once('-').ok_or_else(invalid)?;
+    let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").map_err(|_| invalid())?;
+    let end = match end.trim() {
+        "24:00" => NaiveTime::MIN,
+        end => NaiveTime::parse_from_str(end, "%H:%M").map_err(|_| invalid())?,
+    };
+    if end != NaiveTime::MIN && start >= end {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_parse_crate_datetime_valid() {
+        let parsed = parse_crate_datetime("240101093000").unwrap();
+        let expected = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_parse_crate_datetime_invalid() {
+        assert_eq!(
+            parse_crate_datetime("2024-01-01 09:30:00"),
+            Err(AvailabilityError::InvalidDatetime(
+                "2024-01-01 09:30:00".to_string()
+            ))
+        );
+        assert!(parse_crate_datetime("241301090000").is_err()); // Invalid month
+        assert!(parse_crate_datetime("2401010900").is_err()); // Too short
+        assert!(parse_crate_datetime("").is_err());
+    }
+
+    #[test]
+    fn test_parse_iso_datetime() {
+        let expected = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        assert_eq!(parse_iso_datetime("2024-01-01T09:00:00"), Ok(expected));
+        assert_eq!(parse_iso_datetime("2024-01-01T09:00:00Z"), Ok(expected));
+        // The offset does not shift the wall-clock time
+        assert_eq!(
+            parse_iso_datetime("2024-01-01T09:00:00+02:00"),
+            Ok(expected)
+        );
+        assert_eq!(
+            parse_iso_datetime("2024-01-01 09:00"),
+            Err(AvailabilityError::InvalidDatetime(
+                "2024-01-01 09:00".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_time_window() {
+        let time = |hour, min| NaiveTime::from_hms_opt(hour, min, 0).unwrap();
+        assert_eq!(
+            parse_time_window("09:00-17:00"),
+            Ok((time(9, 0), time(17, 0)))
+        );
+        assert_eq!(
+            parse_time_window("18:30 - 24:00"),
+            Ok((time(18, 30), time(0, 0)))
+        );
+        assert_eq!(
+            parse_time_window("17:00-09:00"),
+            Err(AvailabilityError::InvalidRule(
+                "Invalid time window: 17:00-09:00".to_string()
+            ))
+        );
+        assert!(parse_time_window("09:00").is_err());
+        assert!(parse_time_window("9am-5pm").is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+
+use chrono::{NaiveDateTime, NaiveTime};
+
+/// Errors returned by the availability crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AvailabilityError {
+    /// A datetime string could not be parsed. Holds the offending input.
+    InvalidDatetime(String),
+    /// Frame generation would produce more frames than allowed. Generation stops once the
+    /// limit is exceeded, so `actual` is the number of frames resolved by then.
+    FrameLimitExceeded { max: usize, actual: usize },
+    /// A rule could not be built. Holds the reason.
+    InvalidRule(String),
+    /// Priority 0 does not hold exactly the base rule. Holds a description of the problem.
+    BaseRuleInvariant(String),
+    /// A schedule could not be converted to or from JSON. Holds the reason.
+    InvalidJson(String),
+    /// A schedule could not be read from TOML. Holds the reason.
+    InvalidToml(String),
+    /// Serialized data has a format version this version of the crate cannot read.
+    UnsupportedVersion(u64),
+    /// Priority 0 holds the base rule and cannot be modified.
+    ReservedPriority,
+    /// The new rule overlaps an absolute rule at the same priority.
+    AbsoluteOverlap {
+        priority: usize,
+        new_start: NaiveDateTime,
+        new_end: NaiveDateTime,
+        existing_start: NaiveDateTime,
+        existing_end: NaiveDateTime,
+    },
+    /// The new rule overlaps a relative rule at the same priority on a shared weekday.
+    WeekdayClash {
+        priority: usize,
+        new_start: NaiveDateTime,
+        new_end: NaiveDateTime,
+        existing_start: NaiveDateTime,
+        existing_end: NaiveDateTime,
+    },
+    /// The rule is identical to another rule at the same priority. Holds the priority.
+    DuplicateRule(usize),
+    /// There is no rule at the given priority and index.
+    RuleNotFound { priority: usize, index: usize },
+    /// An "on" rule was added in closed exceptions mode, see
+    /// `Availability::closed_exceptions_mode`.
+    OnRuleInClosedExceptionsMode,
+    /// The start of a rule is not before its end. Returned both by `RuleBuilder::build`
+    /// and wherever a rule is constructed from datetimes directly.
+    StartNotBeforeEnd,
+    /// A weekday name or number was not recognized.
+    InvalidWeekday,
+    /// No start time was set on the builder.
+    MissingStart,
+    /// No end time, or span, was set on the builder.
+    MissingEnd,
+    /// Both an end time and a span were set on the builder.
+    EndAndSpanDays,
+    /// `forever` was combined with a start time, end time or span on the builder.
+    ForeverWithTimes,
+    /// The start time string could not be parsed. Holds the offending input.
+    InvalidStart(String),
+    /// The end time string could not be parsed. Holds the offending input.
+    InvalidEnd(String),
+    /// The start time string is not a valid datetime. Holds the reason, e.g.
+    /// `"Invalid year"`.
+    UnparsableStart(String),
+    /// The end time string is not a valid datetime. Holds the reason, e.g.
+    /// `"Invalid month"`.
+    UnparsableEnd(String),
+    /// An interval string has no `/` separator. Holds the offending input.
+    InvalidInterval(String),
+    /// The start of an interval string could not be parsed. Holds the offending input.
+    InvalidIntervalStart(String),
+    /// The end of an interval string could not be parsed. Holds the offending input.
+    InvalidIntervalEnd(String),
+    /// A relative rule starts and ends on the same day, so it cannot be split into days.
+    SingleDayRelativeRule,
+    /// The span in days puts the end outside the supported range.
+    SpanDaysOutOfRange,
+    /// The rule spans more days than the maximum set on the builder.
+    MaxSpanExceeded { span_days: i64, max_days: i64 },
+    /// The granularity set on the builder is not positive or longer than a day.
+    InvalidGranularity,
+    /// The start or end time of day is not aligned to the granularity set on the builder.
+    Misaligned {
+        /// `"Start"` or `"End"`.
+        boundary: &'static str,
+        time: NaiveTime,
+        granularity_minutes: i64,
+    },
+}
+
+impl fmt::Display for AvailabilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AvailabilityError::InvalidDatetime(input) => {
+                write!(f, "Invalid datetime: {}", input)
+            }
+            AvailabilityError::FrameLimitExceeded { max, actual } => write!(
+                f,
+                "Frame generation would produce at least {} frames, exceeding the limit of {}",
+                actual, max
+            ),
+            AvailabilityError::InvalidRule(reason) => write!(f, "Invalid rule: {}", reason),
+            AvailabilityError::BaseRuleInvariant(reason) => {
+                write!(f, "Base rule invariant violated: {}", reason)
+            }
+            AvailabilityError::InvalidJson(reason) => write!(f, "Invalid JSON: {}", reason),
+            AvailabilityError::InvalidToml(reason) => write!(f, "Invalid TOML: {}", reason),
+            AvailabilityError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported format version: {}", version)
+            }
+            AvailabilityError::ReservedPriority => write!(
+                f,
+                "Priority 0 is reserved for base rule and cannot be modified"
+            ),
+            AvailabilityError::AbsoluteOverlap {
+                priority,
+                new_start,
+                new_end,
+                existing_start,
+                existing_end,
+            } => write!(
+                f,
+                "New rule overlaps with existing rule at priority {}. \
+                    New rule: {:?} to {:?}, Existing rule: {:?} to {:?}",
+                priority, new_start, new_end, existing_start, existing_end
+            ),
+            AvailabilityError::WeekdayClash {
+                priority,
+                new_start,
+                new_end,
+                existing_start,
+                existing_end,
+            } => write!(
+                f,
+                "New rule overlaps with existing rule at priority {} because of clashing weekdays. \
+                        New rule: {:?} to {:?}, Existing rule: {:?} to {:?}",
+                priority, new_start, new_end, existing_start, existing_end
+            ),
+            AvailabilityError::DuplicateRule(priority) => write!(
+                f,
+                "Rule is identical to an existing rule at priority {}",
+                priority
+            ),
+            AvailabilityError::RuleNotFound { priority, index } => write!(
+                f,
+                "Rule index {} does not exist at priority level {}.",
+                index, priority
+            ),
+            AvailabilityError::OnRuleInClosedExceptionsMode => {
+                write!(f, "Only off rules can be added in closed exceptions mode")
+            }
+            AvailabilityError::StartNotBeforeEnd => {
+                write!(f, "Start must not be after or equal to end")
+            }
+            AvailabilityError::InvalidWeekday => write!(f, "Invalid weekday encountered."),
+            AvailabilityError::MissingStart => {
+                write!(f, "Start time is required and was never set")
+            }
+            AvailabilityError::MissingEnd => write!(f, "End time is required and was never set"),
+            AvailabilityError::EndAndSpanDays => {
+                write!(f, "End time and span_days cannot both be set")
+            }
+            AvailabilityError::ForeverWithTimes => {
+                write!(f, "forever cannot be combined with start or end times")
+            }
+            AvailabilityError::InvalidStart(input) => {
+                write!(f, "Invalid start time format: {}", input)
+            }
+            AvailabilityError::InvalidEnd(input) => write!(f, "Invalid end time format: {}", input),
+            AvailabilityError::UnparsableStart(reason) => {
+                write!(f, "Error parsing start: {}", reason)
+            }
+            AvailabilityError::UnparsableEnd(reason) => write!(f, "Error parsing end: {}", reason),
+            AvailabilityError::InvalidInterval(input) => {
+                write!(f, "Invalid interval, missing '/': {}", input)
+            }
+            AvailabilityError::InvalidIntervalStart(input) => {
+                write!(f, "Invalid interval start: {}", input)
+            }
+            AvailabilityError::InvalidIntervalEnd(input) => {
+                write!(f, "Invalid interval end: {}", input)
+            }
+            AvailabilityError::SingleDayRelativeRule => {
+                write!(f, "Rule spans only one day and cannot be divided further")
+            }
+            AvailabilityError::SpanDaysOutOfRange => write!(f, "span_days is out of range"),
+            AvailabilityError::MaxSpanExceeded {
+                span_days,
+                max_days,
+            } => write!(
+                f,
+                "Rule spans {} days, which exceeds the maximum of {} days",
+                span_days, max_days
+            ),
+            AvailabilityError::InvalidGranularity => {
+                write!(f, "Granularity must be positive and at most one day")
+            }
+            AvailabilityError::Misaligned {
+                boundary,
+                time,
+                granularity_minutes,
+            } => write!(
+                f,
+                "{} time {} is not aligned to a granularity of {} minutes",
+                boundary, time, granularity_minutes
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AvailabilityError {}
@@ -1,17 +1,48 @@
 use std::fmt;
 
-use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, Utc};
+use serde::{de::DeserializeOwned, ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
-#[derive(Debug, Clone)]
+/// A resolved stretch of time with its status and payload.
+///
+/// Serializes with an additional computed `duration_seconds` field, which is ignored when
+/// deserializing. End markers also serialize `end_marker`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(bound = "")]
 pub struct Frame<T>
 where
-    T: Serialize + for<'de> Deserialize<'de> + Clone,
+    // Equivalent to `for<'de> Deserialize<'de>`, which clashes with the derived `'de`
+    T: Serialize + DeserializeOwned + Clone,
 {
     pub start: NaiveDateTime,
     pub end: NaiveDateTime,
     pub off: bool,
     pub payload: Option<T>,
+    /// Set on the zero-length frame marking the end of generated frames, see
+    /// `Availability::set_include_end_marker`.
+    #[serde(default)]
+    pub(crate) end_marker: bool,
+}
+
+impl<T> Serialize for Frame<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Frame", 5)?;
+        state.serialize_field("start", &self.start)?;
+        state.serialize_field("end", &self.end)?;
+        state.serialize_field("off", &self.off)?;
+        state.serialize_field("payload", &self.payload)?;
+        state.serialize_field("duration_seconds", &self.duration().num_seconds())?;
+        if self.end_marker {
+            state.serialize_field("end_marker", &self.end_marker)?;
+        }
+        state.end()
+    }
 }
 
 impl<T> fmt::Display for Frame<T>
@@ -50,9 +81,29 @@ where
             end,
             off,
             payload,
+            end_marker: false,
         }
     }
 
+    /// Zero-length frame marking the (exclusive) end of generated frames, carrying the
+    /// status at that instant.
+    pub(crate) fn end_marker(end: NaiveDateTime, off: bool) -> Self {
+        Frame {
+            start: end,
+            end,
+            off,
+            payload: None,
+            end_marker: true,
+        }
+    }
+
+    /// True if this is the zero-length frame marking the end of generated frames. Lets
+    /// the marker be kept when filtering out frames without duration, e.g.
+    /// `frames.retain(|frame| frame.duration() > Duration::zero() || frame.is_end_marker())`.
+    pub fn is_end_marker(&self) -> bool {
+        self.end_marker
+    }
+
     pub fn start_datetime(&self) -> NaiveDateTime {
         self.start
     }
@@ -72,4 +123,106 @@ where
     pub fn payload(&self) -> Option<T> {
         self.payload.clone()
     }
+
+    /// Length of the frame, i.e. `end - start`.
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// Start and end as UTC instants, taking them to be local times at `offset`.
+    pub fn as_utc(&self, offset: FixedOffset) -> (DateTime<Utc>, DateTime<Utc>) {
+        let to_utc = |datetime: NaiveDateTime| {
+            (datetime - Duration::seconds(i64::from(offset.local_minus_utc()))).and_utc()
+        };
+        (to_utc(self.start), to_utc(self.end))
+    }
+
+    /// True if both frames have the same status and payload and their start and end are
+    /// at most `tol` apart, e.g. to compare frames with boundaries drifting by a second.
+    pub fn approx_eq(&self, other: &Frame<T>, tol: Duration) -> bool {
+        (self.start - other.start).abs() <= tol
+            && (self.end - other.end).abs() <= tol
+            && self.off == other.off
+            && serde_json::to_value(&self.payload).ok() == serde_json::to_value(&other.payload).ok()
+    }
+
+    /// Length of the part of the frame that lies within `[start, end)`.
+    pub(crate) fn overlap_with(&self, start: NaiveDateTime, end: NaiveDateTime) -> Duration {
+        let overlap_start = self.start.max(start);
+        let overlap_end = self.end.min(end);
+        if overlap_start < overlap_end {
+            overlap_end - overlap_start
+        } else {
+            Duration::zero()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    fn create_datetime(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_serialize_with_duration() {
+        let frame = Frame::new(
+            create_datetime(2024, 1, 1, 9),
+            create_datetime(2024, 1, 1, 17),
+            false,
+            Some("open".to_string()),
+        );
+
+        let value = serde_json::to_value(&frame).unwrap();
+        assert_eq!(value["duration_seconds"], json!(8 * 60 * 60));
+        assert_eq!(value["start"], json!("2024-01-01T09:00:00"));
+        assert_eq!(value["off"], json!(false));
+
+        // The duration is computed, so it is ignored on input
+        let parsed: Frame<String> = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.start, frame.start);
+        assert_eq!(parsed.end, frame.end);
+        assert_eq!(parsed.payload, frame.payload);
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let frame = Frame::new(
+            create_datetime(2024, 1, 1, 9),
+            create_datetime(2024, 1, 1, 17),
+            false,
+            Some("open".to_string()),
+        );
+        let mut drifted = frame.clone();
+        drifted.end += Duration::seconds(1);
+
+        assert!(frame.approx_eq(&drifted, Duration::seconds(1)));
+        assert!(!frame.approx_eq(&drifted, Duration::zero()));
+
+        // Status and payload must match exactly
+        drifted.payload = Some("closed".to_string());
+        assert!(!frame.approx_eq(&drifted, Duration::seconds(1)));
+    }
+
+    #[test]
+    fn test_as_utc() {
+        let frame: Frame<String> = Frame::new(
+            create_datetime(2024, 6, 1, 9),
+            create_datetime(2024, 6, 1, 17),
+            false,
+            None,
+        );
+        let offset = FixedOffset::east_opt(2 * 60 * 60).unwrap();
+
+        let (start, end) = frame.as_utc(offset);
+        assert_eq!(start, create_datetime(2024, 6, 1, 7).and_utc());
+        assert_eq!(end, create_datetime(2024, 6, 1, 15).and_utc());
+    }
 }
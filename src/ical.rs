@@ -0,0 +1,147 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::error::AvailabilityError;
+
+/// Start and end of a VEVENT.
+pub(crate) type Event = (NaiveDateTime, NaiveDateTime);
+
+/// Parses the start and end of every VEVENT in an iCalendar document.
+///
+/// Datetimes are taken as written, i.e. a `TZID` parameter or a trailing `Z` does not
+/// shift them. Recurring events (with an `RRULE`) and events without a `DTEND` are
+/// rejected.
+pub(crate) fn parse_events(ics: &str) -> Result<Vec<Event>, AvailabilityError> {
+    let mut events = Vec::new();
+    let mut in_event = false;
+    let mut start: Option<NaiveDateTime> = None;
+    let mut end: Option<NaiveDateTime> = None;
+
+    for line in unfold_lines(ics) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters such as `;TZID=Europe/Stockholm` follow the property name
+        let name = name
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        match (name.as_str(), in_event) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VEVENT") => {
+                in_event = true;
+                start = None;
+                end = None;
+            }
+            ("END", true) if value.eq_ignore_ascii_case("VEVENT") => {
+                in_event = false;
+                match (start, end) {
+                    (Some(start), Some(end)) => events.push((start, end)),
+                    (None, _) => {
+                        return Err(AvailabilityError::InvalidRule(
+                            "VEVENT is missing DTSTART".to_string(),
+                        ))
+                    }
+                    (_, None) => {
+                        return Err(AvailabilityError::InvalidRule(
+                            "VEVENT is missing DTEND".to_string(),
+                        ))
+                    }
+                }
+            }
+            ("DTSTART", true) => start = Some(parse_ical_datetime(value)?),
+            ("DTEND", true) => end = Some(parse_ical_datetime(value)?),
+            ("RRULE", true) => {
+                return Err(AvailabilityError::InvalidRule(
+                    "Recurring VEVENTs are not supported".to_string(),
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(events)
+}
+
+/// Joins folded lines, i.e. lines continued on the next line starting with a space or tab.
+fn unfold_lines(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.trim_end().to_string()),
+        }
+    }
+    lines
+}
+
+/// Parses an iCalendar `DATE-TIME` (e.g. `20240101T090000` or `20240101T090000Z`) or
+/// `DATE` (e.g. `20240101`, taken as midnight) value.
+fn parse_ical_datetime(value: &str) -> Result<NaiveDateTime, AvailabilityError> {
+    let value = value.trim();
+    let trimmed = value.strip_suffix('Z').unwrap_or(value);
+    NaiveDateTime::parse_from_str(trimmed, "%Y%m%dT%H%M%S")
+        .or_else(|_| {
+            NaiveDate::parse_from_str(trimmed, "%Y%m%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap())
+        })
+        .map_err(|_| AvailabilityError::InvalidDatetime(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_datetime(year: i32, month: u32, day: u32, hour: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;TZID=Europe/Stockholm:20240101T090000\r\n\
+                   DTEND:20240101T170000Z\r\n\
+                   SUMMARY:Open\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;VALUE=DATE:20240102\r\n\
+                   DTEND;VALUE=DATE:20240103\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+
+        let events = parse_events(ics).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    create_datetime(2024, 1, 1, 9, 0),
+                    create_datetime(2024, 1, 1, 17, 0)
+                ),
+                (
+                    create_datetime(2024, 1, 2, 0, 0),
+                    create_datetime(2024, 1, 3, 0, 0)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_events_rejects_invalid() {
+        let recurring = "BEGIN:VEVENT\nDTSTART:20240101T090000\nDTEND:20240101T170000\n\
+                         RRULE:FREQ=WEEKLY\nEND:VEVENT\n";
+        assert!(parse_events(recurring).is_err());
+
+        let missing_end = "BEGIN:VEVENT\nDTSTART:20240101T090000\nEND:VEVENT\n";
+        assert!(parse_events(missing_end).is_err());
+
+        let invalid = "BEGIN:VEVENT\nDTSTART:tomorrow\nEND:VEVENT\n";
+        assert_eq!(
+            parse_events(invalid),
+            Err(AvailabilityError::InvalidDatetime("tomorrow".to_string()))
+        );
+    }
+}
@@ -1,6 +1,8 @@
 pub mod availability;
 pub mod crate_parameters;
+pub mod error;
 pub mod frame;
+mod ical;
 pub mod rule;
 pub mod rulebuilder;
 pub mod weekdays;
@@ -1,25 +1,43 @@
 use std::fmt;
 
-use chrono::{naive, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
-use serde::{Deserialize, Serialize};
+use chrono::{naive, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
     crate_parameters::{BASE_RULE_YEAR_END, BASE_RULE_YEAR_START},
-    weekdays::{
-        get_days_from_mask, FRIDAY, MONDAY, SATURDAY, SUNDAY, THURSDAY, TUESDAY, WEDNESDAY,
-    },
+    error::AvailabilityError,
+    weekdays::Weekdays,
 };
 
-#[derive(Debug, Clone)]
+/// How a rule applies over its range, see `Rule::kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleKind {
+    /// No weekdays, the rule covers its entire range.
+    Absolute,
+    /// Applies its daily time window on the weekdays in the set.
+    Weekly(Weekdays),
+    /// An empty weekday set. Treated like an absolute rule.
+    EmptyWeekly,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Rule<T>
 where
-    T: Serialize + for<'de> Deserialize<'de> + Clone,
+    // Equivalent to `for<'de> Deserialize<'de>`, which clashes with the derived `'de`
+    T: Serialize + DeserializeOwned + Clone,
 {
     pub start: NaiveDateTime,
     pub end: NaiveDateTime,
-    pub weekdays: Option<u8>,
+    #[serde(with = "crate::weekdays::weekday_names")]
+    pub weekdays: Option<Weekdays>,
     pub off: bool,
     pub payload: Option<T>,
+    /// Timezone the rule's naive datetimes are interpreted in by zoned frame generation.
+    /// Rules without a timezone are taken to be in UTC.
+    #[cfg(feature = "tz")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tz: Option<chrono_tz::Tz>,
 }
 
 impl<T> fmt::Display for Rule<T>
@@ -29,8 +47,8 @@ where
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let status = if self.off { "Off" } else { "On" };
         let weekdays_str = match self.weekdays {
-            Some(mask) => {
-                let days = get_days_from_mask(mask);
+            Some(weekdays) => {
+                let days = weekdays.names();
                 if days.is_empty() {
                     "All Days".to_string()
                 } else {
@@ -61,26 +79,58 @@ where
     pub(crate) fn new(
         start: NaiveDateTime,
         end: NaiveDateTime,
-        weekdays: Option<u8>,
+        weekdays: Option<Weekdays>,
         off: bool,
         payload: Option<T>,
-    ) -> Result<Self, String> {
-        if start >= end {
-            return Err("Start must not be before after or equal to end".to_string());
-        }
-        Ok(Rule {
+    ) -> Result<Self, AvailabilityError> {
+        let rule = Rule {
             start,
             end,
             weekdays,
             off,
             payload,
-        })
+            #[cfg(feature = "tz")]
+            tz: None,
+        };
+        rule.validate()?;
+        Ok(rule)
+    }
+
+    /// Checks the rule on its own: it must have a non-zero duration, only valid weekday
+    /// bits and lie within the range of the base rule. See `Availability::validate_rule`.
+    pub(crate) fn validate(&self) -> Result<(), AvailabilityError> {
+        if self.start >= self.end {
+            return Err(AvailabilityError::StartNotBeforeEnd);
+        }
+        if self
+            .weekdays
+            .is_some_and(|weekdays| Weekdays::from_bits(weekdays.bits()).is_none())
+        {
+            return Err(AvailabilityError::InvalidWeekday);
+        }
+        let base_rule = Rule::<T>::base_rule();
+        if self.start < base_rule.start || self.end > base_rule.end {
+            return Err(AvailabilityError::InvalidRule(format!(
+                "Rule must lie within {} and {}",
+                base_rule.start, base_rule.end
+            )));
+        }
+        Ok(())
+    }
+
+    /// Sets the timezone the rule is interpreted in by zoned frame generation.
+    #[cfg(feature = "tz")]
+    pub fn with_tz(mut self, tz: chrono_tz::Tz) -> Self {
+        self.tz = Some(tz);
+        self
     }
 
     /// Check if rule is active at the given NaiveDateTime.
+    /// Absolute rules are active during their entire range, while relative rules only
+    /// apply their time window on enabled weekdays.
     pub fn is_active(&self, date_time: NaiveDateTime) -> bool {
         match self.is_absolute() {
-            true => self.is_date_time_within(date_time) && self.is_time_within(date_time.time()),
+            true => self.is_date_time_within(date_time),
             false => {
                 if self.is_weekday_enabled(date_time) {
                     if self.off {
@@ -104,6 +154,21 @@ where
         self.is_active(date_time)
     }
 
+    /// True if the rule determines the state at the given NaiveDateTime, whether on or off.
+    /// Absolute rules cover their entire range while relative rules only cover the time
+    /// window on enabled weekdays.
+    pub(crate) fn applies_at(&self, date_time: NaiveDateTime) -> bool {
+        // Cheap bounds check first so most rules are rejected before any weekday math
+        self.bounds_include(date_time) && (self.is_absolute() || self.is_weekday_enabled(date_time))
+    }
+
+    /// True if the NaiveDateTime is within the date range of the rule and, for relative
+    /// rules, within its daily time window. Weekdays are not considered.
+    pub(crate) fn bounds_include(&self, date_time: NaiveDateTime) -> bool {
+        self.is_date_time_within(date_time)
+            && (self.is_absolute() || self.is_time_within(date_time.time()))
+    }
+
     /// Check if two rules overlap in NaiveDateTime.
     pub(crate) fn datetime_overlaps_with(&self, other: &Rule<T>) -> bool {
         self.start < other.end && other.start < self.end
@@ -111,25 +176,33 @@ where
 
     /// Check the day of date_time and returns true if the weekday is enabled in the rule.
     pub fn is_weekday_enabled(&self, date_time: NaiveDateTime) -> bool {
-        let weekday_mask = match date_time.weekday() {
-            Weekday::Mon => MONDAY,
-            Weekday::Tue => TUESDAY,
-            Weekday::Wed => WEDNESDAY,
-            Weekday::Thu => THURSDAY,
-            Weekday::Fri => FRIDAY,
-            Weekday::Sat => SATURDAY,
-            Weekday::Sun => SUNDAY,
-        };
+        let weekday = Weekdays::from_chrono_weekday(date_time.weekday());
 
         self.weekdays
-            .map(|enabled_days| enabled_days & weekday_mask != 0)
+            .map(|enabled_days| enabled_days.intersects(weekday))
             .unwrap_or(false)
     }
 
+    /// Length of the rule, i.e. `end - start`.
+    /// For relative rules this is the full span, not the length of each day's window.
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// Classifies the rule. Unlike `is_absolute`, distinguishes rules without weekdays
+    /// from rules with an empty weekday set.
+    pub fn kind(&self) -> RuleKind {
+        match self.weekdays {
+            None => RuleKind::Absolute,
+            Some(weekdays) if weekdays.is_empty() => RuleKind::EmptyWeekly,
+            Some(weekdays) => RuleKind::Weekly(weekdays),
+        }
+    }
+
     /// True if rule is absolute (i.e. it has not weekdays)
     pub fn is_absolute(&self) -> bool {
         match self.weekdays {
-            Some(weekdays) => weekdays == 0,
+            Some(weekdays) => weekdays.is_empty(),
             None => true,
         }
     }
@@ -137,7 +210,7 @@ where
     /// True if rule is relative (i.e. it has weekdays)
     pub fn is_relative(&self) -> bool {
         match self.weekdays {
-            Some(weekdays) => weekdays != 0,
+            Some(weekdays) => !weekdays.is_empty(),
             None => false,
         }
     }
@@ -149,14 +222,24 @@ where
         time >= self.start && time < self.end
     }
 
+    /// True if the datetime falls within `[start, end)` of the rule, i.e. whether the rule
+    /// governs that period at all. Unlike `is_active`, weekdays and the daily time window
+    /// of relative rules are ignored. Same as `is_date_time_within`.
+    pub fn contains_datetime(&self, datetime: NaiveDateTime) -> bool {
+        self.is_date_time_within(datetime)
+    }
+
     /// True if NaiveTime is within the time range of the rule.
     /// Eg. 2024-01-01 06:00:00 is not within 2024-01-01 09:00:00 to 2024-01-01 17:00:00
+    /// An end time of 00:00:00 means the time range lasts until midnight, so such a rule
+    /// covers every time from its start time onwards, rather than none.
     pub fn is_time_within(&self, time: NaiveTime) -> bool {
-        time >= self.start.time() && time < self.end.time()
+        time >= self.start.time() && (self.end.time() == NaiveTime::MIN || time < self.end.time())
     }
 
-    /// Base rule is always off and covers the entire range of possible dates.
-    /// This has lowest priority and is not modifiable by user.
+    /// Base rule covers the entire range of possible dates and is off by default.
+    /// This has lowest priority and is not modifiable by user, except for its status,
+    /// which `Availability` sets "on" for schedules that are open by default.
     pub(crate) fn base_rule() -> Rule<T> {
         let naive_date_start = NaiveDate::from_ymd_opt(BASE_RULE_YEAR_START, 1, 1).unwrap();
         let naive_date_end = NaiveDate::from_ymd_opt(BASE_RULE_YEAR_END, 1, 1).unwrap();
@@ -167,6 +250,8 @@ where
             weekdays: None,
             off: true,
             payload: None,
+            #[cfg(feature = "tz")]
+            tz: None,
         }
     }
 
@@ -183,68 +268,167 @@ where
         }
     }
 
+    /// True if other has the same window, weekdays, status and payload as self.
+    pub(crate) fn is_identical_to(&self, other: &Rule<T>) -> bool {
+        self.start == other.start
+            && self.end == other.end
+            && self.weekdays == other.weekdays
+            && self.off == other.off
+            && self.has_matching_payload(other).unwrap_or(false)
+            && self.has_matching_tz(other)
+    }
+
+    #[cfg(feature = "tz")]
+    fn has_matching_tz(&self, other: &Rule<T>) -> bool {
+        self.tz == other.tz
+    }
+
+    #[cfg(not(feature = "tz"))]
+    fn has_matching_tz(&self, _other: &Rule<T>) -> bool {
+        true
+    }
+
     /// True if any of the weekdays in self are present in other.
-    /// Eg. 0b00000001 (Monday) is present in 0b00000111 (Monday, Tuesday, Wednesday)
+    /// Eg. Monday is present in Monday, Tuesday and Wednesday
     pub fn has_weekdays_in(&self, other: &Rule<T>) -> bool {
         match (self.weekdays, other.weekdays) {
             (None, _) | (_, None) => false,
             (Some(self_days), Some(other_days)) => {
                 // Check if any of the weekdays in self are present in other
-                self_days & other_days != 0
+                self_days.intersects(other_days)
             }
         }
     }
+
+    /// Merges two rules that overlap or abut into a single rule spanning both.
+    ///
+    /// Returns `None` if the rules cannot be merged without changing what they cover,
+    /// i.e. if they are apart, differ in status, payload or weekdays, or, for relative
+    /// rules, have different daily time windows.
+    pub fn merge(&self, other: &Rule<T>) -> Option<Rule<T>> {
+        let mergeable = self.start <= other.end
+            && other.start <= self.end
+            && self.off == other.off
+            && self.is_absolute() == other.is_absolute()
+            && (self.is_absolute()
+                || (self.weekdays == other.weekdays
+                    && self.start.time() == other.start.time()
+                    && self.end.time() == other.end.time()))
+            && self.has_matching_payload(other).unwrap_or(false)
+            && self.has_matching_tz(other);
+        if !mergeable {
+            return None;
+        }
+
+        let mut merged = self.clone();
+        merged.start = self.start.min(other.start);
+        merged.end = self.end.max(other.end);
+        Some(merged)
+    }
 }
 
 /// Split relative rule to several absolute rules because they can easily be converted to frames.
-pub(crate) fn relative_to_absolute_rules<T>(rule: Rule<T>) -> Result<Vec<Rule<T>>, String>
+pub(crate) fn relative_to_absolute_rules<T>(
+    rule: Rule<T>,
+) -> Result<Vec<Rule<T>>, AvailabilityError>
 where
     T: Serialize + for<'de> Deserialize<'de> + Clone,
 {
-    if rule.is_absolute() {
-        return Ok(vec![rule]);
-    }
-    if rule.start.date() == rule.end.date() {
-        return Err("Rule spans only one day and cannot be divided further".to_string());
-    }
-
-    // Split rule into several rules that span only one day
-    let mut absolute_rules: Vec<Rule<T>> = Vec::new();
-    let mut current_day = rule.start.date();
-
-    while current_day <= rule.end.date() {
-        let current_datetime = current_day.and_hms_opt(0, 0, 0).unwrap();
-
-        if rule.is_weekday_enabled(current_datetime) {
-            let start_time = rule.start.time();
+    let (start, end) = (rule.start, rule.end);
+    relative_to_absolute_rules_in_range(rule, start, end)
+}
 
-            // Create the end time for this day
-            let end_time = rule.end.time();
+/// Like `relative_to_absolute_rules`, but only splits out the days that overlap
+/// `[start, end)`. Avoids expanding every day of long running relative rules when only a
+/// window of them is needed.
+pub(crate) fn relative_to_absolute_rules_in_range<T>(
+    rule: Rule<T>,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Result<Vec<Rule<T>>, AvailabilityError>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    iter_absolute_rules_in_range(rule, start, end)?.collect()
+}
 
-            let start = current_day.and_time(start_time);
-            let end = current_day.and_time(end_time);
+/// Like `relative_to_absolute_rules_in_range`, but splits out the days one at a time, so
+/// the caller can stop early without expanding the whole range.
+pub(crate) fn iter_absolute_rules_in_range<T>(
+    rule: Rule<T>,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Result<impl Iterator<Item = Result<Rule<T>, AvailabilityError>>, AvailabilityError>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    if !rule.is_absolute() && rule.start.date() == rule.end.date() {
+        return Err(AvailabilityError::SingleDayRelativeRule);
+    }
+    // Absolute rules are passed on as they are
+    let (absolute, relative) = match rule.is_absolute() {
+        true => (Some(rule), None),
+        false => (None, Some(rule)),
+    };
 
-            let new_rule = Rule::new(
-                start,
-                end,
-                None, // Convert to absolute rule
-                rule.off,
-                rule.payload.clone(),
-            )?;
+    // Split rule into several rules that span only one day
+    let days = relative.map(|rule| {
+        // Start a day early since a daily window may last until midnight
+        let first_day = rule
+            .start
+            .date()
+            .max(start.date().pred_opt().unwrap_or(start.date()));
+        // End is exclusive, so a window ending at midnight does not include that day
+        let last_day = rule.end.date().min((end - Duration::nanoseconds(1)).date());
+
+        first_day
+            .iter_days()
+            .take_while(move |day| *day <= last_day)
+            .filter_map(move |current_day| {
+                let current_datetime = current_day.and_hms_opt(0, 0, 0).unwrap();
+                if !rule.is_weekday_enabled(current_datetime) {
+                    return None;
+                }
+                let start = current_day.and_time(rule.start.time());
 
-            absolute_rules.push(new_rule);
-        }
+                // Create the end time for this day, where 00:00:00 means until midnight
+                let end = match rule.end.time() {
+                    time if time == NaiveTime::MIN => {
+                        current_day.succ_opt().unwrap().and_time(time)
+                    }
+                    time => current_day.and_time(time),
+                };
 
-        current_day = current_day.succ_opt().unwrap();
-    }
+                // The last day's window may begin at or after the end of the rule
+                if start >= rule.end {
+                    return None;
+                }
 
-    Ok(absolute_rules)
+                let new_rule = Rule::new(
+                    start,
+                    end.min(rule.end),
+                    None, // Convert to absolute rule
+                    rule.off,
+                    rule.payload.clone(),
+                );
+                #[cfg(feature = "tz")]
+                let new_rule = new_rule.map(|mut new_rule| {
+                    new_rule.tz = rule.tz;
+                    new_rule
+                });
+                Some(new_rule)
+            })
+    });
+    Ok(absolute
+        .map(Ok)
+        .into_iter()
+        .chain(days.into_iter().flatten()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::naive::NaiveTime;
+    use chrono::{naive::NaiveTime, Weekday};
     use serde_json::json;
 
     fn create_test_datetime(
@@ -296,12 +480,35 @@ mod tests {
         assert!(!rule.is_open(create_test_datetime(2024, 1, 1, 17, 0, 1)));
     }
 
+    #[test]
+    fn test_multi_day_absolute_rule_is_active() {
+        let start = create_test_datetime(2024, 1, 1, 9, 0, 0);
+        let end = create_test_datetime(2024, 1, 3, 17, 0, 0);
+        let rule = Rule::<String>::new(start, end, None, false, None).unwrap();
+
+        // Interior instants outside the 09:00 to 17:00 time of day
+        assert!(rule.is_active(create_test_datetime(2024, 1, 2, 8, 0, 0)));
+        assert!(rule.is_open(create_test_datetime(2024, 1, 2, 23, 0, 0)));
+        assert!(rule.is_open(create_test_datetime(2024, 1, 3, 16, 59, 59)));
+
+        // Outside the range
+        assert!(!rule.is_active(create_test_datetime(2024, 1, 1, 8, 59, 59)));
+        assert!(!rule.is_active(create_test_datetime(2024, 1, 3, 17, 0, 0)));
+    }
+
     #[test]
     fn test_relative_rule_is_open() {
         let start = create_test_datetime(2024, 1, 1, 9, 0, 0);
         let end = create_test_datetime(2024, 1, 5, 17, 0, 0);
         // Monday and Wednesday only
-        let rule = Rule::<String>::new(start, end, Some(MONDAY | WEDNESDAY), false, None).unwrap();
+        let rule = Rule::<String>::new(
+            start,
+            end,
+            Some(Weekdays::MONDAY | Weekdays::WEDNESDAY),
+            false,
+            None,
+        )
+        .unwrap();
 
         // Monday at valid time
         assert!(rule.is_open(create_test_datetime(2024, 1, 1, 12, 0, 0)));
@@ -372,7 +579,14 @@ mod tests {
     fn test_relative_to_absolute_rules() {
         let start = create_test_datetime(2024, 1, 1, 9, 0, 0); // Monday
         let end = create_test_datetime(2024, 1, 3, 17, 0, 0); // Wednesday
-        let rule = Rule::<String>::new(start, end, Some(MONDAY | WEDNESDAY), false, None).unwrap();
+        let rule = Rule::<String>::new(
+            start,
+            end,
+            Some(Weekdays::MONDAY | Weekdays::WEDNESDAY),
+            false,
+            None,
+        )
+        .unwrap();
 
         let absolute_rules = relative_to_absolute_rules(rule).unwrap();
         assert_eq!(absolute_rules.len(), 2); // Should create two rules (Monday and Wednesday)
@@ -424,16 +638,59 @@ mod tests {
         assert!(!rule4.has_matching_payload(&rule1).unwrap());
     }
 
+    #[test]
+    fn test_is_time_within_until_midnight() {
+        let time = |hour: u32, minute: u32| NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+        let rule = Rule::<String>::new(
+            create_test_datetime(2024, 1, 1, 20, 0, 0),
+            create_test_datetime(2024, 1, 8, 0, 0, 0),
+            Some(Weekdays::MONDAY),
+            false,
+            None,
+        )
+        .unwrap();
+        assert!(!rule.is_time_within(time(19, 59)));
+        assert!(rule.is_time_within(time(20, 0)));
+        assert!(rule.is_time_within(time(23, 59)));
+        assert!(!rule.is_time_within(NaiveTime::MIN));
+
+        // Starting at midnight too, the window covers the whole day
+        let whole_day = Rule::<String>::new(
+            create_test_datetime(2024, 1, 1, 0, 0, 0),
+            create_test_datetime(2024, 1, 8, 0, 0, 0),
+            Some(Weekdays::MONDAY),
+            false,
+            None,
+        )
+        .unwrap();
+        assert!(whole_day.is_time_within(NaiveTime::MIN));
+        assert!(whole_day.is_time_within(time(12, 0)));
+        assert!(whole_day.is_time_within(time(23, 59)));
+    }
+
     #[test]
     fn test_has_weekdays_in() {
         let start = create_test_datetime(2024, 1, 1, 9, 0, 0);
         let end = create_test_datetime(2024, 1, 1, 17, 0, 0);
 
-        let monday_rule = Rule::<String>::new(start, end, Some(MONDAY), false, None).unwrap();
-        let mon_wed_rule =
-            Rule::<String>::new(start, end, Some(MONDAY | WEDNESDAY), false, None).unwrap();
-        let tue_thu_rule =
-            Rule::<String>::new(start, end, Some(TUESDAY | THURSDAY), false, None).unwrap();
+        let monday_rule =
+            Rule::<String>::new(start, end, Some(Weekdays::MONDAY), false, None).unwrap();
+        let mon_wed_rule = Rule::<String>::new(
+            start,
+            end,
+            Some(Weekdays::MONDAY | Weekdays::WEDNESDAY),
+            false,
+            None,
+        )
+        .unwrap();
+        let tue_thu_rule = Rule::<String>::new(
+            start,
+            end,
+            Some(Weekdays::TUESDAY | Weekdays::THURSDAY),
+            false,
+            None,
+        )
+        .unwrap();
         let absolute_rule = Rule::<String>::new(start, end, None, false, None).unwrap();
 
         assert!(monday_rule.has_weekdays_in(&mon_wed_rule));
@@ -442,6 +699,111 @@ mod tests {
         assert!(!monday_rule.has_weekdays_in(&absolute_rule));
     }
 
+    #[test]
+    fn test_duration() {
+        let start = create_test_datetime(2024, 1, 1, 9, 0, 0);
+        let end = create_test_datetime(2024, 1, 4, 9, 0, 0);
+
+        let rule = Rule::<String>::new(start, end, None, false, None).unwrap();
+        assert_eq!(rule.duration(), Duration::days(3));
+
+        // Relative rules report their full span
+        let rule = Rule::<String>::new(start, end, Some(Weekdays::MONDAY), false, None).unwrap();
+        assert_eq!(rule.duration(), Duration::days(3));
+    }
+
+    #[test]
+    fn test_weekdays_serialize_as_names() {
+        let start = create_test_datetime(2024, 1, 1, 9, 0, 0);
+        let end = create_test_datetime(2024, 1, 31, 17, 0, 0);
+        let rule = Rule::<String>::new(
+            start,
+            end,
+            Some(Weekdays::MONDAY | Weekdays::FRIDAY),
+            false,
+            None,
+        )
+        .unwrap();
+
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(value["weekdays"], json!(["monday", "friday"]));
+
+        let parsed: Rule<String> = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(parsed.weekdays, Some(Weekdays::MONDAY | Weekdays::FRIDAY));
+        assert_eq!(parsed.start, start);
+        assert_eq!(parsed.end, end);
+
+        // Absolute rules have no weekdays
+        let rule = Rule::<String>::new(start, end, None, false, None).unwrap();
+        let value = serde_json::to_value(&rule).unwrap();
+        assert!(value["weekdays"].is_null());
+        let parsed: Rule<String> = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.weekdays, None);
+
+        // Invalid day names are rejected
+        let mut invalid = serde_json::to_value(&rule).unwrap();
+        invalid["weekdays"] = json!(["monday", "funday"]);
+        assert!(serde_json::from_value::<Rule<String>>(invalid).is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let first = Rule::new(
+            create_test_datetime(2024, 1, 1, 9, 0, 0),
+            create_test_datetime(2024, 1, 1, 12, 0, 0),
+            None,
+            false,
+            Some("a".to_string()),
+        )
+        .unwrap();
+        let second = Rule::new(
+            create_test_datetime(2024, 1, 1, 12, 0, 0),
+            create_test_datetime(2024, 1, 1, 17, 0, 0),
+            None,
+            false,
+            Some("a".to_string()),
+        )
+        .unwrap();
+
+        let merged = first.merge(&second).unwrap();
+        assert_eq!(merged.start, create_test_datetime(2024, 1, 1, 9, 0, 0));
+        assert_eq!(merged.end, create_test_datetime(2024, 1, 1, 17, 0, 0));
+        assert_eq!(merged.payload, Some("a".to_string()));
+        assert!(second.merge(&first).unwrap().is_identical_to(&merged));
+
+        // Differing payloads cannot be merged
+        let mut other_payload = second.clone();
+        other_payload.payload = Some("b".to_string());
+        assert!(first.merge(&other_payload).is_none());
+
+        // Neither can rules that are apart
+        let later = Rule::new(
+            create_test_datetime(2024, 1, 2, 9, 0, 0),
+            create_test_datetime(2024, 1, 2, 17, 0, 0),
+            None,
+            false,
+            Some("a".to_string()),
+        )
+        .unwrap();
+        assert!(first.merge(&later).is_none());
+    }
+
+    #[test]
+    fn test_kind() {
+        let start = create_test_datetime(2024, 1, 1, 9, 0, 0);
+        let end = create_test_datetime(2024, 1, 31, 17, 0, 0);
+
+        let rule = Rule::<String>::new(start, end, None, false, None).unwrap();
+        assert_eq!(rule.kind(), RuleKind::Absolute);
+
+        let rule = Rule::<String>::new(start, end, Some(Weekdays::all()), false, None).unwrap();
+        assert_eq!(rule.kind(), RuleKind::Weekly(Weekdays::all()));
+
+        let rule = Rule::<String>::new(start, end, Some(Weekdays::empty()), false, None).unwrap();
+        assert_eq!(rule.kind(), RuleKind::EmptyWeekly);
+        assert!(rule.is_absolute());
+    }
+
     #[test]
     fn test_base_rule() {
         let base_rule = Rule::<String>::base_rule();
@@ -451,4 +813,25 @@ mod tests {
         assert_eq!(base_rule.start.year(), BASE_RULE_YEAR_START);
         assert_eq!(base_rule.end.year(), BASE_RULE_YEAR_END);
     }
+
+    #[test]
+    fn test_contains_datetime() {
+        // Mondays 09:00 to 17:00 during January
+        let rule = Rule::<String>::new(
+            create_test_datetime(2024, 1, 1, 9, 0, 0),
+            create_test_datetime(2024, 1, 31, 17, 0, 0),
+            Some(Weekdays::MONDAY),
+            false,
+            None,
+        )
+        .unwrap();
+
+        // A Wednesday night is outside the window and weekdays, but inside the span
+        let wednesday_night = create_test_datetime(2024, 1, 10, 22, 0, 0);
+        assert!(rule.contains_datetime(wednesday_night));
+        assert!(!rule.is_active(wednesday_night));
+
+        assert!(!rule.contains_datetime(create_test_datetime(2024, 1, 1, 8, 0, 0)));
+        assert!(!rule.contains_datetime(create_test_datetime(2024, 1, 31, 17, 0, 0)));
+    }
 }
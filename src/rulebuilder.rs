@@ -1,8 +1,9 @@
-use chrono::NaiveDateTime;
+use chrono::{Duration, NaiveDateTime, Timelike};
 use serde::{Deserialize, Serialize};
 
-use crate::rule::Rule;
-use crate::weekdays::{FRIDAY, MONDAY, SATURDAY, SUNDAY, THURSDAY, TUESDAY, WEDNESDAY};
+use crate::error::AvailabilityError;
+use crate::rule::{relative_to_absolute_rules, Rule};
+use crate::weekdays::Weekdays;
 
 #[derive(Default)]
 pub struct RuleBuilder<T>
@@ -11,9 +12,16 @@ where
 {
     start_str: Option<String>,
     end_str: Option<String>,
-    weekdays: Option<u8>,
+    weekdays: Option<Weekdays>,
     off: bool,
     payload: Option<T>,
+    span_days: Option<i64>,
+    forever: bool,
+    max_span: Option<Duration>,
+    granularity: Option<Duration>,
+    #[cfg(feature = "tz")]
+    tz: Option<chrono_tz::Tz>,
+    error: Option<AvailabilityError>,
 }
 
 impl<T> RuleBuilder<T>
@@ -29,6 +37,13 @@ where
             weekdays: None,
             off: false,
             payload: None,
+            span_days: None,
+            forever: false,
+            max_span: None,
+            granularity: None,
+            #[cfg(feature = "tz")]
+            tz: None,
+            error: None,
         }
     }
 
@@ -80,76 +95,151 @@ where
         self
     }
 
+    /// Sets both start and end of the rule from an ISO 8601 style interval string.
+    ///
+    /// The interval must be two datetimes in the `"YYYY-MM-DD HH:MM:SS"` format separated
+    /// by a `/`. For example, `"2024-01-01 09:00:00/2024-01-31 17:00:00"`.
+    ///
+    /// **Note:** Like the other setters this method does not fail. A malformed interval
+    ///  is reported by the `build()` method which returns a `Result`.
+    pub fn interval_str(mut self, interval: &str) -> Self {
+        let Some((start, end)) = interval.split_once('/') else {
+            self.error = Some(AvailabilityError::InvalidInterval(interval.to_string()));
+            return self;
+        };
+        match (
+            NaiveDateTime::parse_from_str(start.trim(), "%Y-%m-%d %H:%M:%S"),
+            NaiveDateTime::parse_from_str(end.trim(), "%Y-%m-%d %H:%M:%S"),
+        ) {
+            (Ok(start), Ok(end)) => self.start_datetime(start).end_datetime(end),
+            (Err(_), _) => {
+                self.error = Some(AvailabilityError::InvalidIntervalStart(start.to_string()));
+                self
+            }
+            (_, Err(_)) => {
+                self.error = Some(AvailabilityError::InvalidIntervalEnd(end.to_string()));
+                self
+            }
+        }
+    }
+
+    /// Sets the end of the rule to `days` calendar days after its start, at the same
+    /// time of day.
+    ///
+    /// The end is resolved in the `build()` method, so the start may be set before or
+    /// after calling this. Cannot be combined with an explicit end time.
+    pub fn span_days(mut self, days: i64) -> Self {
+        self.span_days = Some(days);
+        self
+    }
+
+    /// Makes the rule span the entire range of the base rule.
+    ///
+    /// Relative rules built this way cover the whole of each enabled weekday. Cannot be
+    /// combined with explicit start or end times.
+    pub fn forever(mut self) -> Self {
+        self.forever = true;
+        self
+    }
+
+    /// Sets the maximum span the rule may have, i.e. `end - start`.
+    ///
+    /// A rule spanning decades is usually a typo, so `build()` returns an error if the
+    /// span exceeds this maximum.
+    pub fn max_span(mut self, max_span: Duration) -> Self {
+        self.max_span = Some(max_span);
+        self
+    }
+
+    /// Requires the start and end time of day to be aligned to `granularity`, e.g. quarter
+    /// hours, which `build()` enforces.
+    pub fn require_granularity(mut self, granularity: Duration) -> Self {
+        self.granularity = Some(granularity);
+        self
+    }
+
     /// Sets the weekdays on which the rule is active using a slice of string slices.
     ///
     /// Each string should represent a day of the week, such as `"monday"`, `"tue"`, etc.
     /// The method is case-insensitive and accepts both full names and common abbreviations.
     ///
     /// If **any** string in the slice is invalid (i.e., does not correspond to a valid weekday),
-    /// the builder sets a special sentinel bit pattern (`0xFF`) to indicate the presence of an
-    /// invalid weekday. The `build()` method will then detect this and return an error.
+    /// the `build()` method will return an `InvalidWeekday` error.
     pub fn weekdays(mut self, days: &[&str]) -> Self {
-        let mut mask = self.weekdays.unwrap_or(0);
+        let mut weekdays = self.weekdays.unwrap_or_else(Weekdays::empty);
         for day in days {
-            match day.to_lowercase().as_str() {
-                "monday" | "mon" => mask |= MONDAY,
-                "tuesday" | "tue" => mask |= TUESDAY,
-                "wednesday" | "wed" => mask |= WEDNESDAY,
-                "thursday" | "thu" => mask |= THURSDAY,
-                "friday" | "fri" => mask |= FRIDAY,
-                "saturday" | "sat" => mask |= SATURDAY,
-                "sunday" | "sun" => mask |= SUNDAY,
-                _ => {
-                    // Sentinel for "invalid weekday"
-                    mask = 0xFF;
+            match Weekdays::from_day(day) {
+                Some(day) => weekdays |= day,
+                None => {
+                    self.error = Some(AvailabilityError::InvalidWeekday);
                     // Break early because at least one weekday was invalid.
                     break;
                 }
             };
         }
-        self.weekdays = Some(mask);
+        self.weekdays = Some(weekdays);
+        self
+    }
+
+    /// Sets the weekdays on which the rule is active using ISO weekday numbers, i.e. `1`
+    /// for Monday through `7` for Sunday.
+    ///
+    /// Like `weekdays()`, any number outside `1..=7` makes the `build()` method return an
+    /// error.
+    pub fn weekday_numbers(mut self, numbers: &[u8]) -> Self {
+        let mut weekdays = self.weekdays.unwrap_or_else(Weekdays::empty);
+        for number in numbers {
+            match number {
+                1..=7 => weekdays |= Weekdays::from_mask(1 << (number - 1)),
+                _ => {
+                    self.error = Some(AvailabilityError::InvalidWeekday);
+                    break;
+                }
+            }
+        }
+        self.weekdays = Some(weekdays);
         self
     }
 
     /// Adds Monday to the set of active weekdays for the rule.
     pub fn monday(mut self) -> Self {
-        let val = self.weekdays.unwrap_or(0) | MONDAY;
+        let val = self.weekdays.unwrap_or_else(Weekdays::empty) | Weekdays::MONDAY;
         self.weekdays = Some(val);
         self
     }
     /// Adds Tuesday to the set of active weekdays for the rule.
     pub fn tuesday(mut self) -> Self {
-        let val = self.weekdays.unwrap_or(0) | TUESDAY;
+        let val = self.weekdays.unwrap_or_else(Weekdays::empty) | Weekdays::TUESDAY;
         self.weekdays = Some(val);
         self
     }
     /// Adds Wednesday to the set of active weekdays for the rule.
     pub fn wednesday(mut self) -> Self {
-        let val = self.weekdays.unwrap_or(0) | WEDNESDAY;
+        let val = self.weekdays.unwrap_or_else(Weekdays::empty) | Weekdays::WEDNESDAY;
         self.weekdays = Some(val);
         self
     }
     /// Adds Thursday to the set of active weekdays for the rule.
     pub fn thursday(mut self) -> Self {
-        let val = self.weekdays.unwrap_or(0) | THURSDAY;
+        let val = self.weekdays.unwrap_or_else(Weekdays::empty) | Weekdays::THURSDAY;
         self.weekdays = Some(val);
         self
     }
     /// Adds Friday to the set of active weekdays for the rule.
     pub fn friday(mut self) -> Self {
-        let val = self.weekdays.unwrap_or(0) | FRIDAY;
+        let val = self.weekdays.unwrap_or_else(Weekdays::empty) | Weekdays::FRIDAY;
         self.weekdays = Some(val);
         self
     }
     /// Adds Saturday to the set of active weekdays for the rule.
     pub fn saturday(mut self) -> Self {
-        let val = self.weekdays.unwrap_or(0) | SATURDAY;
+        let val = self.weekdays.unwrap_or_else(Weekdays::empty) | Weekdays::SATURDAY;
         self.weekdays = Some(val);
         self
     }
     /// Adds Sunday to the set of active weekdays for the rule.
     pub fn sunday(mut self) -> Self {
-        let val = self.weekdays.unwrap_or(0) | SUNDAY;
+        let val = self.weekdays.unwrap_or_else(Weekdays::empty) | Weekdays::SUNDAY;
         self.weekdays = Some(val);
         self
     }
@@ -167,6 +257,13 @@ where
         self
     }
 
+    /// Sets the timezone the rule is interpreted in by zoned frame generation.
+    #[cfg(feature = "tz")]
+    pub fn tz(mut self, tz: chrono_tz::Tz) -> Self {
+        self.tz = Some(tz);
+        self
+    }
+
     /// Attaches a custom payload to the rule.
     ///
     /// The payload can be any type that implements `Serialize`, `Deserialize`, and `Clone`.
@@ -182,6 +279,8 @@ where
     /// # Example
     ///
     /// ```rust
+    /// # use availability::rulebuilder::RuleBuilder;
+    /// # use serde_json::json;
     /// let payload = json!({"description": "Maintenance Window"});
     /// let builder = RuleBuilder::new()
     ///     .payload(payload);
@@ -197,56 +296,140 @@ where
     /// 1. Ensures that both start and end times are set.
     /// 2. Validates the format and correctness of the datetime strings.
     /// 3. Checks that the start time precedes the end time.
-    /// 4. Validates the weekdays, ensuring no invalid weekdays were set.
     ///
-    /// If all validations pass, it returns an `Ok(Rule<T>)`. Otherwise, it returns an `Err(String)`
-    /// containing an error message.
+    /// If all validations pass, it returns an `Ok(Rule<T>)`. Otherwise, it returns an
+    /// `Err(AvailabilityError)` describing the failure.
     ///
     /// # Errors
     ///
-    /// - Returns an error if either the start or end time is not set.
-    /// - Returns an error if the datetime strings are improperly formatted or invalid.
-    /// - Returns an error if the start time is not before the end time.
-    /// - Returns an error if invalid weekdays were specified.
+    /// - `InvalidInterval`, `InvalidIntervalStart` or `InvalidIntervalEnd` if an interval
+    ///   string could not be parsed.
+    /// - `InvalidWeekday` if invalid weekdays were specified.
+    /// - `MissingStart` or `MissingEnd` if either the start or end time is not set.
+    /// - `InvalidStart` or `InvalidEnd` if the datetime strings are improperly formatted.
+    /// - `UnparsableStart` or `UnparsableEnd` if the datetime strings are not valid
+    ///   datetimes, e.g. have a month of 13.
+    /// - `StartNotBeforeEnd` if the start time is not before the end time.
+    /// - `MaxSpanExceeded` if the rule spans more than the maximum span, if one was set.
+    /// - `Misaligned` if the start or end time is not aligned to the required granularity.
+    /// - `InvalidRule` if the rule does not lie within the range of the base rule, e.g.
+    ///   after a very large span.
     ///
     /// # Returns
     ///
     /// - `Ok(Rule<T>)` if the rule is successfully built.
-    /// - `Err(String)` containing an error message if validation fails.
-    pub fn build(self) -> Result<Rule<T>, String> {
-        // First, ensure we had a start/end string
-        let start_str = self
-            .start_str
-            .ok_or("Start time is required and was never set")?;
-        let end_str = self
-            .end_str
-            .ok_or("End time is required and was never set")?;
-
-        // Validate they are each 12 chars
-        if start_str.len() != 12 {
-            return Err(format!("Invalid start time format: {}", start_str));
-        }
-        if end_str.len() != 12 {
-            return Err(format!("Invalid end time format: {}", end_str));
+    /// - `Err(AvailabilityError)` if validation fails.
+    pub fn build(self) -> Result<Rule<T>, AvailabilityError> {
+        // Errors deferred from setters take precedence
+        if let Some(error) = self.error {
+            return Err(error);
         }
 
-        // Parse them both
-        let start =
-            parse_datetime(&start_str).map_err(|e| format!("Error parsing start: {}", e))?;
-        let end = parse_datetime(&end_str).map_err(|e| format!("Error parsing end: {}", e))?;
+        let (start, end) = if self.forever {
+            if self.start_str.is_some() || self.end_str.is_some() || self.span_days.is_some() {
+                return Err(AvailabilityError::ForeverWithTimes);
+            }
+            // The base range cannot be expressed in the two digit year string format
+            let base_rule = Rule::<T>::base_rule();
+            (base_rule.start, base_rule.end)
+        } else {
+            // First, ensure we had a start/end string
+            let start_str = self.start_str.ok_or(AvailabilityError::MissingStart)?;
+            let end_str = match (self.end_str, self.span_days) {
+                (Some(_), Some(_)) => {
+                    return Err(AvailabilityError::EndAndSpanDays);
+                }
+                (Some(end_str), None) => Some(end_str),
+                (None, Some(_)) => None,
+                (None, None) => return Err(AvailabilityError::MissingEnd),
+            };
+
+            // Validate they are each 12 chars
+            if start_str.len() != 12 {
+                return Err(AvailabilityError::InvalidStart(start_str));
+            }
+            if let Some(end_str) = &end_str {
+                if end_str.len() != 12 {
+                    return Err(AvailabilityError::InvalidEnd(end_str.clone()));
+                }
+            }
+
+            // Parse them both, or derive end from start when spanning days
+            let start = parse_datetime(&start_str).map_err(AvailabilityError::UnparsableStart)?;
+            let end = match (end_str, self.span_days) {
+                (Some(end_str), _) => {
+                    parse_datetime(&end_str).map_err(AvailabilityError::UnparsableEnd)?
+                }
+                (None, days) => Duration::try_days(days.unwrap_or_default())
+                    .and_then(|span| start.checked_add_signed(span))
+                    .ok_or(AvailabilityError::SpanDaysOutOfRange)?,
+            };
+            (start, end)
+        };
 
         // Additional validation: ensure start < end
         if start >= end {
-            return Err("Start must not be after or equal to end".into());
+            return Err(AvailabilityError::StartNotBeforeEnd);
         }
 
-        // Weekday check: 0xFF => we encountered an invalid weekday in `.weekdays()`
-        if self.weekdays == Some(0xFF) {
-            return Err("Invalid weekday encountered.".into());
+        // Catch typos like a year 2999 end when a maximum span was set
+        if let Some(max_span) = self.max_span {
+            if end - start > max_span {
+                return Err(AvailabilityError::MaxSpanExceeded {
+                    span_days: (end - start).num_days(),
+                    max_days: max_span.num_days(),
+                });
+            }
+        }
+
+        if let Some(granularity) = self.granularity {
+            if granularity <= Duration::zero() || granularity > Duration::days(1) {
+                return Err(AvailabilityError::InvalidGranularity);
+            }
+            for (boundary, datetime) in [("Start", start), ("End", end)] {
+                if !is_aligned(datetime, granularity) {
+                    return Err(AvailabilityError::Misaligned {
+                        boundary,
+                        time: datetime.time(),
+                        granularity_minutes: granularity.num_minutes(),
+                    });
+                }
+            }
         }
 
         // If all is good, build the actual `Rule`
-        Rule::new(start, end, self.weekdays, self.off, self.payload)
+        #[cfg_attr(not(feature = "tz"), allow(unused_mut))]
+        let mut rule = Rule::new(start, end, self.weekdays, self.off, self.payload)?;
+        #[cfg(feature = "tz")]
+        {
+            rule.tz = self.tz;
+        }
+        Ok(rule)
+    }
+
+    /// Builds the rule like `build()` and splits it into the absolute rules it covers,
+    /// one per enabled weekday. An absolute rule is returned as is.
+    ///
+    /// This is the same expansion that is done when generating frames.
+    pub fn build_many(self) -> Result<Vec<Rule<T>>, AvailabilityError> {
+        let rule = self.build()?;
+        relative_to_absolute_rules(rule)
+    }
+}
+
+impl RuleBuilder<serde_json::Value> {
+    /// Attaches a JSON payload to the rule, for builders with `serde_json::Value` payloads.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use availability::rulebuilder::RuleBuilder;
+    /// # use serde_json::json;
+    /// let builder = RuleBuilder::new()
+    ///     .payload_json(&json!({"description": "Maintenance Window"}));
+    /// ```
+    pub fn payload_json(self, payload: &serde_json::Value) -> Self {
+        self.payload(payload.clone())
     }
 }
 
@@ -277,6 +460,17 @@ fn parse_datetime(datetime_str: &str) -> Result<NaiveDateTime, String> {
         .ok_or("Invalid time".to_string())
 }
 
+/// Whether the time of day of `datetime` is a whole multiple of `granularity`.
+fn is_aligned(datetime: NaiveDateTime, granularity: Duration) -> bool {
+    let time = datetime.time();
+    let since_midnight = Duration::seconds(i64::from(time.num_seconds_from_midnight()))
+        + Duration::nanoseconds(i64::from(time.nanosecond()));
+    since_midnight
+        .num_nanoseconds()
+        .zip(granularity.num_nanoseconds())
+        .is_some_and(|(since_midnight, granularity)| since_midnight % granularity == 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -325,7 +519,10 @@ mod tests {
             .build()
             .unwrap();
 
-        assert_eq!(rule.weekdays, Some(MONDAY | WEDNESDAY | FRIDAY));
+        assert_eq!(
+            rule.weekdays,
+            Some(Weekdays::MONDAY | Weekdays::WEDNESDAY | Weekdays::FRIDAY)
+        );
 
         // Test with short forms
         let rule = RuleBuilder::<String>::new()
@@ -335,7 +532,10 @@ mod tests {
             .build()
             .unwrap();
 
-        assert_eq!(rule.weekdays, Some(MONDAY | WEDNESDAY | FRIDAY));
+        assert_eq!(
+            rule.weekdays,
+            Some(Weekdays::MONDAY | Weekdays::WEDNESDAY | Weekdays::FRIDAY)
+        );
     }
 
     #[test]
@@ -349,7 +549,10 @@ mod tests {
             .build()
             .unwrap();
 
-        assert_eq!(rule.weekdays, Some(MONDAY | WEDNESDAY | FRIDAY));
+        assert_eq!(
+            rule.weekdays,
+            Some(Weekdays::MONDAY | Weekdays::WEDNESDAY | Weekdays::FRIDAY)
+        );
     }
 
     #[test]
@@ -365,6 +568,19 @@ mod tests {
         assert_eq!(rule.payload.unwrap(), payload);
     }
 
+    #[test]
+    fn test_builder_with_payload_json() {
+        let payload = json!({"status": "active"});
+        let rule = RuleBuilder::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101170000")
+            .payload_json(&payload)
+            .build()
+            .unwrap();
+
+        assert_eq!(rule.payload.unwrap(), payload);
+    }
+
     #[test]
     fn test_builder_with_off() {
         let rule = RuleBuilder::<String>::new()
@@ -386,7 +602,33 @@ mod tests {
             .build();
 
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Invalid weekday encountered.");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Invalid weekday encountered."
+        );
+    }
+
+    #[test]
+    fn test_builder_weekday_numbers() {
+        let rule = RuleBuilder::<String>::new()
+            .start_time_str("240101090000")
+            .end_time_str("240131170000")
+            .weekday_numbers(&[1, 5])
+            .build()
+            .unwrap();
+        assert_eq!(rule.weekdays, Some(Weekdays::MONDAY | Weekdays::FRIDAY));
+
+        for invalid in [0, 8] {
+            let result = RuleBuilder::<String>::new()
+                .start_time_str("240101090000")
+                .end_time_str("240131170000")
+                .weekday_numbers(&[invalid])
+                .build();
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "Invalid weekday encountered."
+            );
+        }
     }
 
     #[test]
@@ -414,7 +656,7 @@ mod tests {
             .build();
         assert!(result.is_err());
         assert_eq!(
-            result.unwrap_err(),
+            result.unwrap_err().to_string(),
             "Start time is required and was never set"
         );
 
@@ -424,7 +666,7 @@ mod tests {
             .build();
         assert!(result.is_err());
         assert_eq!(
-            result.unwrap_err(),
+            result.unwrap_err().to_string(),
             "End time is required and was never set"
         );
 
@@ -434,7 +676,28 @@ mod tests {
             .end_time_str("240101170000")
             .build();
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Invalid start time format"));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid start time format"));
+
+        // Right length, but not a valid datetime
+        let result = RuleBuilder::<String>::new()
+            .start_time_str("xx0101090000")
+            .end_time_str("240101170000")
+            .build();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Error parsing start: Invalid year"
+        );
+        let result = RuleBuilder::<String>::new()
+            .start_time_str("240101090000")
+            .end_time_str("241301170000")
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            AvailabilityError::UnparsableEnd("Invalid date".to_string())
+        );
 
         // End before start
         let result = RuleBuilder::<String>::new()
@@ -443,7 +706,7 @@ mod tests {
             .build();
         assert!(result.is_err());
         assert_eq!(
-            result.unwrap_err(),
+            result.unwrap_err().to_string(),
             "Start must not be after or equal to end"
         );
 
@@ -454,11 +717,151 @@ mod tests {
             .build();
         assert!(result.is_err());
         assert_eq!(
-            result.unwrap_err(),
+            result.unwrap_err().to_string(),
             "Start must not be after or equal to end"
         );
     }
 
+    #[test]
+    fn test_builder_interval_str() {
+        let rule = RuleBuilder::<String>::new()
+            .interval_str("2024-01-01 09:00:00/2024-01-31 17:00:00")
+            .build()
+            .unwrap();
+        assert_eq!(
+            rule.start.format("%y%m%d%H%M%S").to_string(),
+            "240101090000"
+        );
+        assert_eq!(rule.end.format("%y%m%d%H%M%S").to_string(), "240131170000");
+
+        // Missing separator
+        let result = RuleBuilder::<String>::new()
+            .interval_str("2024-01-01 09:00:00 2024-01-31 17:00:00")
+            .build();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("missing '/'"));
+
+        // Malformed half
+        let result = RuleBuilder::<String>::new()
+            .interval_str("2024-01-01 09:00:00/2024-01-31")
+            .build();
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid interval end"));
+    }
+
+    #[test]
+    fn test_builder_span_days() {
+        let rule = RuleBuilder::<String>::new()
+            .start_time_str("240101090000")
+            .span_days(3)
+            .build()
+            .unwrap();
+        assert!(rule.is_absolute());
+        assert_eq!(rule.end.format("%y%m%d%H%M%S").to_string(), "240104090000");
+
+        // Explicit end and span_days are mutually exclusive
+        let result = RuleBuilder::<String>::new()
+            .start_time_str("240101090000")
+            .end_time_str("240102090000")
+            .span_days(3)
+            .build();
+        assert!(result.is_err());
+
+        // Non-positive spans end up before or at start
+        let result = RuleBuilder::<String>::new()
+            .start_time_str("240101090000")
+            .span_days(0)
+            .build();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Start must not be after or equal to end"
+        );
+    }
+
+    #[test]
+    fn test_builder_require_granularity() {
+        let quarter_hour = Duration::minutes(15);
+
+        let result = RuleBuilder::<String>::new()
+            .start_time_str("240101090700")
+            .end_time_str("240101170000")
+            .require_granularity(quarter_hour)
+            .build();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Start time 09:07:00 is not aligned to a granularity of 15 minutes"
+        );
+
+        let result = RuleBuilder::<String>::new()
+            .start_time_str("240101091500")
+            .end_time_str("240101170000")
+            .require_granularity(quarter_hour)
+            .build();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_builder_max_span() {
+        let ten_years = Duration::days(10 * 365);
+
+        // The base range spans a thousand years
+        let result = RuleBuilder::<String>::new()
+            .forever()
+            .max_span(ten_years)
+            .build();
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("exceeds the maximum of 3650 days"));
+
+        let result = RuleBuilder::<String>::new()
+            .start_time_str("240101090000")
+            .end_time_str("280101090000")
+            .max_span(ten_years)
+            .build();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_builder_build_many() {
+        // Monday 2024-01-01 to Sunday 2024-01-07
+        let rules = RuleBuilder::<String>::new()
+            .start_time_str("240101090000")
+            .end_time_str("240107170000")
+            .monday()
+            .wednesday()
+            .build_many()
+            .unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert!(rules.iter().all(|rule| rule.is_absolute()));
+        assert_eq!(
+            rules[0].start.format("%y%m%d%H%M%S").to_string(),
+            "240101090000"
+        );
+        assert_eq!(
+            rules[1].end.format("%y%m%d%H%M%S").to_string(),
+            "240103170000"
+        );
+
+        // Build errors are propagated
+        let result = RuleBuilder::<String>::new()
+            .start_time_str("240101090000")
+            .build_many();
+        assert_eq!(result.err(), Some(AvailabilityError::MissingEnd));
+
+        // A relative rule within a single day cannot be split
+        let result = RuleBuilder::<String>::new()
+            .start_time_str("240101090000")
+            .end_time_str("240101170000")
+            .monday()
+            .build_many();
+        assert_eq!(result.err(), Some(AvailabilityError::SingleDayRelativeRule));
+    }
+
     #[test]
     fn test_idempotent_weekday_setting() {
         // Test single day multiple times
@@ -471,8 +874,8 @@ mod tests {
             .build()
             .unwrap();
 
-        // Should only have SATURDAY bit set once
-        assert_eq!(rule.weekdays, Some(SATURDAY));
+        // Should only have the Saturday bit set once
+        assert_eq!(rule.weekdays, Some(Weekdays::SATURDAY));
 
         // Test multiple days with repetition
         let rule = RuleBuilder::<String>::new()
@@ -487,6 +890,9 @@ mod tests {
             .unwrap();
 
         // Should have exactly these three days set
-        assert_eq!(rule.weekdays, Some(MONDAY | WEDNESDAY | FRIDAY));
+        assert_eq!(
+            rule.weekdays,
+            Some(Weekdays::MONDAY | Weekdays::WEDNESDAY | Weekdays::FRIDAY)
+        );
     }
 }
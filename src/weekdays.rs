@@ -1,3 +1,49 @@
+use bitflags::bitflags;
+use chrono::Weekday;
+
+bitflags! {
+    /// Set of days of the week a relative rule applies on.
+    ///
+    /// Only the seven weekday bits exist, so constructing a set with other bits requires
+    /// `from_bits_retain`. Set operations such as `union`, `intersection`, `difference`
+    /// and `complement` come with the type. Convert from the raw `u8` constants with
+    /// `Weekdays::from_mask` and back with `bits`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Weekdays: u8 {
+        const MONDAY = MONDAY;
+        const TUESDAY = TUESDAY;
+        const WEDNESDAY = WEDNESDAY;
+        const THURSDAY = THURSDAY;
+        const FRIDAY = FRIDAY;
+        const SATURDAY = SATURDAY;
+        const SUNDAY = SUNDAY;
+    }
+}
+
+impl Weekdays {
+    /// The single day of a chrono `Weekday`.
+    pub fn from_chrono_weekday(weekday: Weekday) -> Self {
+        Weekdays::from_bits_truncate(get_mask_from_weekday(weekday))
+    }
+
+    /// The single day of a name. Case-insensitive and accepts both full names and three
+    /// letter abbreviations, e.g. `"monday"` or `"Mon"`.
+    pub fn from_day(day: &str) -> Option<Self> {
+        get_mask_from_day(day).map(Weekdays::from_mask)
+    }
+
+    /// Days of a raw mask built from the `u8` constants. Bits outside the seven weekdays
+    /// are dropped.
+    pub fn from_mask(mask: u8) -> Self {
+        Weekdays::from_bits_truncate(mask)
+    }
+
+    /// Lowercase names of the days in the set, from Monday to Sunday.
+    pub fn names(self) -> Vec<&'static str> {
+        get_days_from_mask(self.bits())
+    }
+}
+
 pub const MONDAY: u8 = 1;
 pub const TUESDAY: u8 = 2;
 pub const WEDNESDAY: u8 = 4;
@@ -5,7 +51,9 @@ pub const THURSDAY: u8 = 8;
 pub const FRIDAY: u8 = 16;
 pub const SATURDAY: u8 = 32;
 pub const SUNDAY: u8 = 64;
+pub const ALL_WEEKDAYS: u8 = MONDAY | TUESDAY | WEDNESDAY | THURSDAY | FRIDAY | SATURDAY | SUNDAY;
 
+/// Lowercase names of the days in a raw mask. Legacy `u8` helper, see `Weekdays::names`.
 pub fn get_days_from_mask(mask: u8) -> Vec<&'static str> {
     let mut days = Vec::new();
     if mask & MONDAY != 0 {
@@ -32,6 +80,87 @@ pub fn get_days_from_mask(mask: u8) -> Vec<&'static str> {
     days
 }
 
+/// Mask of a single day from its name. Case-insensitive and accepts both full names
+/// and three letter abbreviations, e.g. `"monday"` or `"Mon"`. Legacy `u8` helper, see
+/// `Weekdays::from_day`.
+pub fn get_mask_from_day(day: &str) -> Option<u8> {
+    match day.to_lowercase().as_str() {
+        "monday" | "mon" => Some(MONDAY),
+        "tuesday" | "tue" => Some(TUESDAY),
+        "wednesday" | "wed" => Some(WEDNESDAY),
+        "thursday" | "thu" => Some(THURSDAY),
+        "friday" | "fri" => Some(FRIDAY),
+        "saturday" | "sat" => Some(SATURDAY),
+        "sunday" | "sun" => Some(SUNDAY),
+        _ => None,
+    }
+}
+
+/// Mask of a single day from a chrono `Weekday`. Legacy `u8` helper, see
+/// `Weekdays::from_chrono_weekday`.
+pub fn get_mask_from_weekday(weekday: Weekday) -> u8 {
+    match weekday {
+        Weekday::Mon => MONDAY,
+        Weekday::Tue => TUESDAY,
+        Weekday::Wed => WEDNESDAY,
+        Weekday::Thu => THURSDAY,
+        Weekday::Fri => FRIDAY,
+        Weekday::Sat => SATURDAY,
+        Weekday::Sun => SUNDAY,
+    }
+}
+
+/// Serde adapter (de)serializing optional weekdays as an array of day names,
+/// e.g. `["monday", "friday"]`. Use with `#[serde(with = "crate::weekdays::weekday_names")]`.
+pub mod weekday_names {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Weekdays;
+
+    pub fn serialize<S>(weekdays: &Option<Weekdays>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        weekdays.map(Weekdays::names).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Weekdays>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Some(days) = Option::<Vec<String>>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        let mut weekdays = Weekdays::empty();
+        for day in days.iter() {
+            weekdays |= Weekdays::from_day(day)
+                .ok_or_else(|| D::Error::custom(format!("invalid weekday: {}", day)))?;
+        }
+        Ok(Some(weekdays))
+    }
+}
+
+/// Days present in either mask. Legacy `u8` helper, see `Weekdays::union`.
+pub fn weekdays_union(a: u8, b: u8) -> u8 {
+    a | b
+}
+
+/// Days present in both masks. Legacy `u8` helper, see `Weekdays::intersection`.
+pub fn weekdays_intersection(a: u8, b: u8) -> u8 {
+    a & b
+}
+
+/// Days present in `a` but not in `b`. Legacy `u8` helper, see `Weekdays::difference`.
+pub fn weekdays_difference(a: u8, b: u8) -> u8 {
+    a & !b
+}
+
+/// Days not present in the mask. Only the seven valid weekday bits are set. Legacy `u8`
+/// helper, see `Weekdays::complement`.
+pub fn weekdays_complement(mask: u8) -> u8 {
+    !mask & ALL_WEEKDAYS
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,4 +256,74 @@ mod tests {
             vec!["monday", "tuesday", "wednesday"]
         );
     }
+
+    #[test]
+    fn test_weekdays_set_operations() {
+        let mon_wed = MONDAY | WEDNESDAY;
+        let wed_fri = WEDNESDAY | FRIDAY;
+
+        assert_eq!(
+            weekdays_union(mon_wed, wed_fri),
+            MONDAY | WEDNESDAY | FRIDAY
+        );
+        assert_eq!(weekdays_intersection(mon_wed, wed_fri), WEDNESDAY);
+        assert_eq!(weekdays_intersection(MONDAY, FRIDAY), 0);
+        assert_eq!(weekdays_difference(mon_wed, wed_fri), MONDAY);
+        assert_eq!(weekdays_difference(mon_wed, mon_wed), 0);
+    }
+
+    #[test]
+    fn test_weekdays_complement() {
+        let weekdays = MONDAY | TUESDAY | WEDNESDAY | THURSDAY | FRIDAY;
+        assert_eq!(weekdays_complement(weekdays), SATURDAY | SUNDAY);
+        assert_eq!(weekdays_complement(0), ALL_WEEKDAYS);
+        assert_eq!(weekdays_complement(ALL_WEEKDAYS), 0);
+
+        // Never sets bits outside the valid weekdays
+        assert_eq!(weekdays_complement(MONDAY) & !ALL_WEEKDAYS, 0);
+        assert_eq!(weekdays_complement(0b10000000), ALL_WEEKDAYS);
+    }
+
+    #[test]
+    fn test_weekdays_flags() {
+        assert_eq!(Weekdays::MONDAY.bits(), MONDAY);
+        assert_eq!(Weekdays::all().bits(), ALL_WEEKDAYS);
+        assert_eq!(
+            Weekdays::from_chrono_weekday(Weekday::Wed),
+            Weekdays::WEDNESDAY
+        );
+
+        // Bits outside the seven weekdays are dropped
+        assert_eq!(Weekdays::from_mask(MONDAY | 0b10000000), Weekdays::MONDAY);
+        assert!(Weekdays::from_bits(0xFF).is_none());
+
+        let mon_wed = Weekdays::MONDAY | Weekdays::WEDNESDAY;
+        assert!(mon_wed.intersects(Weekdays::WEDNESDAY | Weekdays::FRIDAY));
+        assert!(!mon_wed.intersects(Weekdays::TUESDAY));
+        assert_eq!(
+            mon_wed.iter().collect::<Vec<_>>(),
+            vec![Weekdays::MONDAY, Weekdays::WEDNESDAY]
+        );
+        assert_eq!(mon_wed.names(), vec!["monday", "wednesday"]);
+
+        // Equivalents of the u8 helpers
+        assert_eq!(Weekdays::from_day("Fri"), Some(Weekdays::FRIDAY));
+        assert_eq!(Weekdays::from_day("funday"), None);
+        assert_eq!(
+            mon_wed.complement(),
+            Weekdays::from_mask(weekdays_complement(MONDAY | WEDNESDAY))
+        );
+        assert_eq!(
+            mon_wed.difference(Weekdays::WEDNESDAY | Weekdays::FRIDAY),
+            Weekdays::MONDAY
+        );
+    }
+
+    #[test]
+    fn test_get_mask_from_day() {
+        assert_eq!(get_mask_from_day("monday"), Some(MONDAY));
+        assert_eq!(get_mask_from_day("Fri"), Some(FRIDAY));
+        assert_eq!(get_mask_from_day("SUNDAY"), Some(SUNDAY));
+        assert_eq!(get_mask_from_day("funday"), None);
+    }
 }